@@ -5,11 +5,37 @@ use std::path::PathBuf;
 
 fn main() {
     println!("cargo:rustc-link-lib=uvc");
+    println!("cargo:rustc-link-lib=usb-1.0");
+
+    // Homebrew does not install into the default search paths on macOS
+    let mut clang_args = Vec::new();
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("macos") {
+        println!("cargo:rerun-if-env-changed=HOMEBREW_PREFIX");
+        let prefix = env::var("HOMEBREW_PREFIX").unwrap_or_else(|_| {
+            if env::var("CARGO_CFG_TARGET_ARCH").as_deref() == Ok("aarch64") {
+                "/opt/homebrew".to_string()
+            } else {
+                "/usr/local".to_string()
+            }
+        });
+        println!("cargo:rustc-link-search=native={}/lib", prefix);
+        clang_args.push(format!("-I{}/include", prefix));
+        // libuvc.h includes <libusb.h> without the libusb-1.0/ prefix
+        clang_args.push(format!("-I{}/include/libusb-1.0", prefix));
+    }
 
     let bindings = bindgen::Builder::default()
         .header("wrapper.h")
+        .clang_args(clang_args)
         .allowlist_function("uvc_.*")
         .allowlist_type("uvc_.*")
+        .allowlist_function("libusb_get_device")
+        .allowlist_function("libusb_get_active_config_descriptor")
+        .allowlist_function("libusb_free_config_descriptor")
+        .allowlist_function("libusb_reset_device")
+        .allowlist_function("libusb_get_device_speed")
+        .allowlist_type("libusb_speed")
+        .allowlist_type("libusb_class_code")
         .blocklist_item("uvc_format_desc_union_")
         .generate()
         .expect("Failed to generate bindings");
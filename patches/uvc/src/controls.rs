@@ -0,0 +1,259 @@
+use crate::device::DeviceHandle;
+use crate::error::{Error, Result};
+use uvc_sys::*;
+
+#[derive(Copy, Clone, Debug)]
+pub enum ScanningMode {
+    Interlaced,
+    Progressive,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum AutoExposureMode {
+    Manual,
+    Auto,
+    ShutterPriority,
+    AperturePriority,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum AutoExposurePriority {
+    Constant,
+    Variable,
+}
+
+impl<'a> DeviceHandle<'a> {
+    pub fn scanning_mode(&self) -> Result<ScanningMode> {
+        unsafe {
+            let mut mode = std::mem::MaybeUninit::uninit();
+            let err = uvc_get_scanning_mode(
+                self.devh.as_ptr(),
+                mode.as_mut_ptr(),
+                uvc_req_code_UVC_GET_CUR,
+            )
+            .into();
+            if err != Error::Success {
+                return Err(err);
+            }
+            match mode.assume_init() {
+                0 => Ok(ScanningMode::Interlaced),
+                1 => Ok(ScanningMode::Progressive),
+                _ => Err(Error::Other),
+            }
+        }
+    }
+    pub fn ae_mode(&self) -> Result<AutoExposureMode> {
+        unsafe {
+            let mut mode = std::mem::MaybeUninit::uninit();
+            let err = uvc_get_ae_mode(
+                self.devh.as_ptr(),
+                mode.as_mut_ptr(),
+                uvc_req_code_UVC_GET_CUR,
+            )
+            .into();
+            if err != Error::Success {
+                return Err(err);
+            }
+            match mode.assume_init() {
+                1 => Ok(AutoExposureMode::Manual),
+                2 => Ok(AutoExposureMode::Auto),
+                4 => Ok(AutoExposureMode::ShutterPriority),
+                8 => Ok(AutoExposureMode::AperturePriority),
+                _ => Err(Error::Other),
+            }
+        }
+    }
+    pub fn ae_priority(&self) -> Result<AutoExposurePriority> {
+        unsafe {
+            let mut priority = std::mem::MaybeUninit::uninit();
+            let err = uvc_get_ae_priority(
+                self.devh.as_ptr(),
+                priority.as_mut_ptr(),
+                uvc_req_code_UVC_GET_CUR,
+            )
+            .into();
+            if err != Error::Success {
+                return Err(err);
+            }
+            match priority.assume_init() {
+                0 => Ok(AutoExposurePriority::Constant),
+                1 => Ok(AutoExposurePriority::Variable),
+                _ => Err(Error::Other),
+            }
+        }
+    }
+    /// Sets whether auto-exposure may vary the frame rate
+    pub fn set_ae_priority(&self, priority: AutoExposurePriority) -> Result<()> {
+        let priority = match priority {
+            AutoExposurePriority::Constant => 0,
+            AutoExposurePriority::Variable => 1,
+        };
+        unsafe {
+            let err = uvc_set_ae_priority(self.devh.as_ptr(), priority).into();
+            if err == Error::Success {
+                Ok(())
+            } else {
+                Err(err)
+            }
+        }
+    }
+    pub fn exposure_abs(&self) -> Result<u32> {
+        unsafe {
+            let mut time = std::mem::MaybeUninit::uninit();
+            let err = uvc_get_exposure_abs(
+                self.devh.as_ptr(),
+                time.as_mut_ptr(),
+                uvc_req_code_UVC_GET_CUR,
+            )
+            .into();
+            if err == Error::Success {
+                Ok(time.assume_init())
+            } else {
+                Err(err)
+            }
+        }
+    }
+    pub fn exposure_rel(&self) -> Result<i8> {
+        unsafe {
+            let mut step = std::mem::MaybeUninit::uninit();
+            let err = uvc_get_exposure_rel(
+                self.devh.as_ptr(),
+                step.as_mut_ptr(),
+                uvc_req_code_UVC_GET_CUR,
+            )
+            .into();
+            if err == Error::Success {
+                Ok(step.assume_init())
+            } else {
+                Err(err)
+            }
+        }
+    }
+    pub fn focus_abs(&self) -> Result<u16> {
+        unsafe {
+            let mut focus = std::mem::MaybeUninit::uninit();
+            let err = uvc_get_focus_abs(
+                self.devh.as_ptr(),
+                focus.as_mut_ptr(),
+                uvc_req_code_UVC_GET_CUR,
+            )
+            .into();
+            if err == Error::Success {
+                Ok(focus.assume_init())
+            } else {
+                Err(err)
+            }
+        }
+    }
+    pub fn focus_rel(&self) -> Result<(i8, u8)> {
+        unsafe {
+            let mut focus_rel = std::mem::MaybeUninit::uninit();
+            let mut speed = std::mem::MaybeUninit::uninit();
+            let err = uvc_get_focus_rel(
+                self.devh.as_ptr(),
+                focus_rel.as_mut_ptr(),
+                speed.as_mut_ptr(),
+                uvc_req_code_UVC_GET_CUR,
+            )
+            .into();
+            if err == Error::Success {
+                Ok((focus_rel.assume_init(), speed.assume_init()))
+            } else {
+                Err(err)
+            }
+        }
+    }
+
+    /// Reads the current value of a control of an extension unit
+    pub fn extension_control(&self, unit: u8, selector: u8) -> Result<Vec<u8>> {
+        unsafe {
+            let len = uvc_get_ctrl_len(self.devh.as_ptr(), unit, selector);
+            if len < 0 {
+                return Err(Error::from(len as uvc_error_t));
+            }
+            let mut data = vec![0u8; len as usize];
+            let read = uvc_get_ctrl(
+                self.devh.as_ptr(),
+                unit,
+                selector,
+                data.as_mut_ptr().cast(),
+                len,
+                uvc_req_code_UVC_GET_CUR,
+            );
+            if read < 0 {
+                return Err(Error::from(read as uvc_error_t));
+            }
+            data.truncate(read as usize);
+            Ok(data)
+        }
+    }
+    /// Sets a control of an extension unit
+    pub fn set_extension_control(&self, unit: u8, selector: u8, data: &[u8]) -> Result<()> {
+        self.set_ctrl(unit, selector, data)
+    }
+
+    /// IDs of the camera terminals that have an absolute exposure time
+    /// control, one per sensor on cameras that expose them separately
+    pub fn exposure_terminals(&self) -> Vec<u8> {
+        let mut ids = Vec::new();
+        unsafe {
+            let mut terminal = uvc_get_input_terminals(self.devh.as_ptr());
+            while !terminal.is_null() {
+                let t = &*terminal;
+                // D3 of bmControls is Exposure Time (Absolute)
+                if t.wTerminalType == uvc_it_type_UVC_ITT_CAMERA && t.bmControls & (1 << 3) != 0 {
+                    ids.push(t.bTerminalID);
+                }
+                terminal = t.next;
+            }
+        }
+        ids
+    }
+    /// IDs of the processing units that have a gain control
+    pub fn gain_units(&self) -> Vec<u8> {
+        let mut ids = Vec::new();
+        unsafe {
+            let mut unit = uvc_get_processing_units(self.devh.as_ptr());
+            while !unit.is_null() {
+                let u = &*unit;
+                // D9 of bmControls is Gain
+                if u.bmControls & (1 << 9) != 0 {
+                    ids.push(u.bUnitID);
+                }
+                unit = u.next;
+            }
+        }
+        ids
+    }
+    /// Switches a camera terminal to manual exposure and sets its exposure
+    /// time, in units of 100 microseconds
+    pub fn set_exposure_abs_on(&self, terminal: u8, time: u32) -> Result<()> {
+        self.set_ctrl(terminal, uvc_ct_ctrl_selector_UVC_CT_AE_MODE_CONTROL as u8, &[1])?;
+        self.set_ctrl(
+            terminal,
+            uvc_ct_ctrl_selector_UVC_CT_EXPOSURE_TIME_ABSOLUTE_CONTROL as u8,
+            &time.to_le_bytes(),
+        )
+    }
+    /// Sets the gain of a processing unit
+    pub fn set_gain_on(&self, unit: u8, gain: u16) -> Result<()> {
+        self.set_ctrl(unit, uvc_pu_ctrl_selector_UVC_PU_GAIN_CONTROL as u8, &gain.to_le_bytes())
+    }
+
+    fn set_ctrl(&self, unit: u8, selector: u8, data: &[u8]) -> Result<()> {
+        unsafe {
+            let written = uvc_set_ctrl(
+                self.devh.as_ptr(),
+                unit,
+                selector,
+                data.as_ptr() as *mut _,
+                data.len() as _,
+            );
+            if written < 0 {
+                Err(Error::from(written as uvc_error_t))
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
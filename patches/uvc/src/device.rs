@@ -0,0 +1,738 @@
+use std::ffi::CStr;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::slice;
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::formats::{FrameFormat, StreamFormat};
+use crate::streaming::StreamHandle;
+use uvc_sys::*;
+
+unsafe impl<'a> Send for DeviceList<'a> {}
+unsafe impl<'a> Sync for DeviceList<'a> {}
+#[derive(Debug)]
+/// List of camera devices, iterate to get the device(s)
+pub struct DeviceList<'a> {
+    start: *mut *mut uvc_device,
+    list: NonNull<*mut uvc_device>,
+    _ph: PhantomData<&'a &'a uvc_device>,
+}
+
+impl<'a> Drop for DeviceList<'a> {
+    fn drop(&mut self) {
+        unsafe { uvc_free_device_list(self.start, false as u8) }
+    }
+}
+
+impl<'a> DeviceList<'a> {
+    pub(crate) fn new(list: NonNull<*mut uvc_device>) -> Self {
+        Self {
+            start: list.as_ptr(),
+            list,
+            _ph: PhantomData,
+        }
+    }
+}
+
+impl<'a> Iterator for DeviceList<'a> {
+    type Item = Device<'a>;
+
+    fn next(&mut self) -> Option<Device<'a>> {
+        let item = self.list.as_ptr();
+        if unsafe { (*item).is_null() } {
+            return None;
+        }
+
+        let device = unsafe { Device::from_raw(*item) };
+        self.list = unsafe { NonNull::new(self.list.as_ptr().add(1)).unwrap() };
+
+        Some(device)
+    }
+}
+
+unsafe impl<'a> Send for Device<'a> {}
+unsafe impl<'a> Sync for Device<'a> {}
+#[derive(Debug)]
+/// Device that can be opened
+pub struct Device<'a> {
+    dev: NonNull<uvc_device>,
+    _dev: PhantomData<&'a uvc_device>,
+}
+
+impl<'a> Drop for Device<'a> {
+    fn drop(&mut self) {
+        unsafe { uvc_unref_device(self.dev.as_ptr()) };
+    }
+}
+
+impl<'a> Device<'a> {
+    pub(crate) unsafe fn from_raw(dev: *mut uvc_device) -> Self {
+        Device {
+            dev: NonNull::new(dev).unwrap(),
+            _dev: PhantomData,
+        }
+    }
+    /// Create handle to a device
+    pub fn open(&'a self) -> Result<DeviceHandle<'a>> {
+        unsafe {
+            let mut devh = std::mem::MaybeUninit::uninit();
+            let err = uvc_open(self.dev.as_ptr(), devh.as_mut_ptr()).into();
+            match err {
+                Error::Success => Ok(DeviceHandle {
+                    devh: NonNull::new(devh.assume_init()).unwrap(),
+                    _devh: PhantomData,
+                }),
+                err => Err(err),
+            }
+        }
+    }
+    /// Get the description of a device
+    pub fn description(&self) -> Result<DeviceDescription> {
+        unsafe {
+            let mut desc = std::mem::MaybeUninit::uninit();
+            let err = uvc_get_device_descriptor(self.dev.as_ptr(), desc.as_mut_ptr()).into();
+            if err != Error::Success {
+                return Err(err);
+            }
+
+            let desc = desc.assume_init();
+
+            let vendor_id = (*desc).idVendor;
+            let product_id = (*desc).idProduct;
+            let bcd_uvc = (*desc).bcdUVC;
+
+            let serial_number_c_str = (*desc).serialNumber;
+            let serial_number = if serial_number_c_str.is_null() {
+                None
+            } else {
+                Some(
+                    CStr::from_ptr(serial_number_c_str)
+                        .to_owned()
+                        .into_string()
+                        .unwrap(),
+                )
+            };
+            let manufacturer_c_str = (*desc).manufacturer;
+            let manufacturer = if manufacturer_c_str.is_null() {
+                None
+            } else {
+                Some(
+                    CStr::from_ptr(manufacturer_c_str)
+                        .to_owned()
+                        .into_string()
+                        .unwrap(),
+                )
+            };
+            let product_c_str = (*desc).product;
+            let product = if product_c_str.is_null() {
+                None
+            } else {
+                Some(
+                    CStr::from_ptr(product_c_str)
+                        .to_owned()
+                        .into_string()
+                        .unwrap(),
+                )
+            };
+            let descp = Ok(DeviceDescription {
+                vendor_id,
+                product_id,
+                bcd_uvc,
+                serial_number,
+                manufacturer,
+                product,
+            });
+
+            uvc_free_device_descriptor(desc);
+
+            descp
+        }
+    }
+
+    /// Bus number of which this device is connected
+    #[must_use]
+    pub fn bus_number(&self) -> u8 {
+        unsafe { uvc_get_bus_number(self.dev.as_ptr()) }
+    }
+
+    /// Device address within the bus
+    #[must_use]
+    pub fn device_address(&self) -> u8 {
+        unsafe { uvc_get_device_address(self.dev.as_ptr()) }
+    }
+}
+
+/// Speed the device is connected with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UsbSpeed {
+    Unknown,
+    /// USB 1.0 low speed, 1.5 Mbit/s
+    Low,
+    /// USB 1.1 full speed, 12 Mbit/s
+    Full,
+    /// USB 2.0 high speed, 480 Mbit/s
+    High,
+    /// USB 3.0 SuperSpeed, 5 Gbit/s
+    Super,
+    /// USB 3.1 SuperSpeed+, 10 Gbit/s
+    SuperPlus,
+}
+
+unsafe impl<'a> Send for DeviceHandle<'a> {}
+unsafe impl<'a> Sync for DeviceHandle<'a> {}
+#[derive(Debug)]
+/// Open handle to a device
+pub struct DeviceHandle<'a> {
+    pub(crate) devh: NonNull<uvc_device_handle>,
+    _devh: PhantomData<&'a uvc_device_handle>,
+}
+
+impl<'a, 'b> DeviceHandle<'a> {
+    /// List all supported formats
+    #[must_use]
+    pub fn supported_formats(&self) -> FormatDescriptors<'a> {
+        unsafe {
+            let format_descs = uvc_get_format_descs(self.devh.as_ptr());
+
+            FormatDescriptors {
+                head: format_descs,
+                _ph: PhantomData,
+            }
+        }
+    }
+
+    /// Iterates over all available formats to select the best format.
+    ///
+    /// f should compare (x, y) and return the preferred format.
+    pub fn get_preferred_format<F>(&self, f: F) -> Option<StreamFormat>
+    where
+        F: Fn(StreamFormat, StreamFormat) -> StreamFormat,
+    {
+        let mut pref_format = None;
+        for i in self.supported_formats() {
+            for j in i.supported_formats() {
+                for k in j.intervals() {
+                    let format = StreamFormat {
+                        width: u32::from(j.width()),
+                        height: u32::from(j.height()),
+                        fps: 10_000_000 / *k,
+                        format: match j.subtype() {
+                            DescriptionSubtype::FormatMJPEG | DescriptionSubtype::FrameMJPEG => {
+                                FrameFormat::MJPEG
+                            }
+                            DescriptionSubtype::FormatUncompressed
+                            | DescriptionSubtype::FrameUncompressed => FrameFormat::Uncompressed,
+                            _ => FrameFormat::Any,
+                        },
+                    };
+                    pref_format = Some(pref_format.map_or(format, |x| f(x, format)));
+                }
+            }
+        }
+        pref_format
+    }
+
+    /// Creates a stream handle
+    pub fn get_stream_handle_with_format_size_and_fps(
+        &'a self,
+        format: FrameFormat,
+        width: u32,
+        height: u32,
+        fps: u32,
+    ) -> Result<StreamHandle<'a>> {
+        unsafe {
+            let mut handle = std::mem::MaybeUninit::uninit();
+            let err = uvc_get_stream_ctrl_format_size(
+                self.devh.as_ptr(),
+                handle.as_mut_ptr(),
+                format.into(),
+                width as i32,
+                height as i32,
+                fps as i32,
+            )
+            .into();
+            if err == Error::Success {
+                Ok(StreamHandle {
+                    handle: handle.assume_init(),
+                    devh: self,
+                })
+            } else {
+                Err(err)
+            }
+        }
+    }
+
+    /// Creates a stream handle
+    pub fn get_stream_handle_with_format(
+        &'a self,
+        format: StreamFormat,
+    ) -> Result<StreamHandle<'a>> {
+        self.get_stream_handle_with_format_size_and_fps(
+            format.format,
+            format.width,
+            format.height,
+            format.fps,
+        )
+    }
+}
+
+// Class-specific video streaming descriptor subtypes (UVC 1.5, A.6)
+const VS_FORMAT_UNCOMPRESSED: u8 = 0x04;
+const VS_FRAME_UNCOMPRESSED: u8 = 0x05;
+const VS_FORMAT_MJPEG: u8 = 0x06;
+const VS_FRAME_MJPEG: u8 = 0x07;
+const CS_INTERFACE: u8 = 0x24;
+
+// GUID of the YUY2 uncompressed format
+const GUID_YUY2: [u8; 16] = [
+    b'Y', b'U', b'Y', b'2', 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
+// Calls `f` with the format index and every frame descriptor of `format` in
+// the class-specific descriptors of one streaming interface, in descriptor
+// order, until it returns Some
+fn find_frame<T>(
+    descriptors: &[u8],
+    format: FrameFormat,
+    mut f: impl FnMut(u8, &[u8]) -> Option<T>,
+) -> Option<T> {
+    let (format_subtype, frame_subtype) = match format {
+        FrameFormat::MJPEG => (VS_FORMAT_MJPEG, VS_FRAME_MJPEG),
+        FrameFormat::YUYV | FrameFormat::Uncompressed => {
+            (VS_FORMAT_UNCOMPRESSED, VS_FRAME_UNCOMPRESSED)
+        }
+        _ => return None,
+    };
+
+    let mut format_index = None;
+    let mut rest = descriptors;
+    while let [length, kind, ..] = *rest {
+        let length = length as usize;
+        if length < 3 || length > rest.len() {
+            break;
+        }
+        let (descriptor, next) = rest.split_at(length);
+        rest = next;
+        if kind != CS_INTERFACE {
+            continue;
+        }
+
+        match descriptor[2] {
+            subtype if subtype == format_subtype && length >= 4 => {
+                let matches = format != FrameFormat::YUYV
+                    || descriptor.get(5..21) == Some(GUID_YUY2.as_slice());
+                format_index = matches.then_some(descriptor[3]);
+            }
+            VS_FORMAT_UNCOMPRESSED | VS_FORMAT_MJPEG => format_index = None,
+            subtype if subtype == frame_subtype && length >= 26 => {
+                let Some(format_index) = format_index else {
+                    continue;
+                };
+                if let Some(found) = f(format_index, descriptor) {
+                    return Some(found);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Width and height of a frame descriptor found by find_frame()
+fn frame_size(descriptor: &[u8]) -> (u32, u32) {
+    let word = |offset: usize| u16::from_le_bytes([descriptor[offset], descriptor[offset + 1]]);
+    (u32::from(word(5)), u32::from(word(7)))
+}
+
+// Finds the format index, frame index and frame interval of `format` in the
+// class-specific descriptors of one streaming interface, matching like
+// uvc_get_stream_ctrl_format_size() does
+fn find_stream_format(descriptors: &[u8], format: StreamFormat) -> Option<(u8, u8, u32)> {
+    let fps = format.fps.max(1);
+    find_frame(descriptors, format.format, |format_index, descriptor| {
+        let length = descriptor.len();
+        let dword = |offset: usize| {
+            let bytes = &descriptor[offset..offset + 4];
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        };
+        if frame_size(descriptor) != (format.width, format.height) {
+            return None;
+        }
+
+        let interval_type = descriptor[25] as usize;
+        let interval = if interval_type == 0 {
+            // Continuous: minimum, maximum and step
+            if length < 38 {
+                return None;
+            }
+            let (min, max, step) = (dword(26), dword(30), dword(34));
+            let interval = 10_000_000 / fps;
+            let in_range =
+                (min..=max).contains(&interval) && (step == 0 || (interval - min) % step == 0);
+            in_range.then_some(interval)
+        } else {
+            (0..interval_type)
+                .map(|i| 26 + i * 4)
+                .take_while(|&offset| offset + 4 <= length)
+                .map(dword)
+                .find(|&interval| interval != 0 && 10_000_000 / interval == fps)
+        };
+        interval.map(|interval| (format_index, descriptor[3], interval))
+    })
+}
+
+// Class-specific descriptors following a streaming interface's descriptor
+fn extra_descriptors<'d>(altsetting: &'d libusb_interface_descriptor) -> &'d [u8] {
+    if altsetting.extra.is_null() || altsetting.extra_length <= 0 {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(altsetting.extra, altsetting.extra_length as usize) }
+    }
+}
+
+impl<'a> DeviceHandle<'a> {
+    /// Interface numbers of the video streaming interfaces, in descriptor order
+    pub fn streaming_interfaces(&self) -> Result<Vec<u8>> {
+        self.with_streaming_interfaces(|interfaces| {
+            interfaces
+                .iter()
+                .map(|altsetting| altsetting.bInterfaceNumber)
+                .collect()
+        })
+    }
+
+    // Calls `f` with the first altsetting of every video streaming interface
+    // of the active configuration
+    fn with_streaming_interfaces<T>(
+        &self,
+        f: impl FnOnce(&[&libusb_interface_descriptor]) -> T,
+    ) -> Result<T> {
+        // libuvc does not expose its list of streaming interfaces, so walk
+        // the active configuration descriptor through libusb instead
+        const SC_VIDEOSTREAMING: u8 = 0x02;
+
+        unsafe {
+            let usb_dev = libusb_get_device(uvc_get_libusb_handle(self.devh.as_ptr()));
+            let mut config = std::mem::MaybeUninit::uninit();
+            let err = Error::from(libusb_get_active_config_descriptor(
+                usb_dev,
+                config.as_mut_ptr(),
+            ));
+            if err != Error::Success {
+                return Err(err);
+            }
+            let config = config.assume_init();
+
+            let interfaces =
+                slice::from_raw_parts((*config).interface, (*config).bNumInterfaces as usize);
+            let streaming = interfaces
+                .iter()
+                .filter(|interface| interface.num_altsetting > 0)
+                .map(|interface| &*interface.altsetting)
+                .filter(|altsetting| {
+                    altsetting.bInterfaceClass == libusb_class_code_LIBUSB_CLASS_VIDEO as u8
+                        && altsetting.bInterfaceSubClass == SC_VIDEOSTREAMING
+                })
+                .collect::<Vec<_>>();
+            let result = f(&streaming);
+
+            libusb_free_config_descriptor(config);
+
+            Ok(result)
+        }
+    }
+
+    /// Frame sizes the given streaming interface lists for `format`, in
+    /// descriptor order and without repeats
+    ///
+    /// Unlike `supported_formats()`, which only covers the first streaming
+    /// interface, this reads the descriptors of `interface_number` itself.
+    pub fn frame_sizes_on_interface(
+        &self,
+        format: FrameFormat,
+        interface_number: u8,
+    ) -> Result<Vec<(u32, u32)>> {
+        self.with_streaming_interfaces(|interfaces| {
+            let mut sizes = Vec::new();
+            let altsetting = interfaces
+                .iter()
+                .find(|altsetting| altsetting.bInterfaceNumber == interface_number);
+            if let Some(altsetting) = altsetting {
+                find_frame::<()>(extra_descriptors(altsetting), format, |_, descriptor| {
+                    let size = frame_size(descriptor);
+                    if !sizes.contains(&size) {
+                        sizes.push(size);
+                    }
+                    None
+                });
+            }
+            sizes
+        })
+    }
+
+    /// Resets the USB port of the device
+    ///
+    /// Returns `Error::NotFound` if the device re-enumerated, in that case
+    /// it has to be found and opened again.
+    pub fn reset_port(&self) -> Result<()> {
+        let err = unsafe {
+            Error::from(libusb_reset_device(uvc_get_libusb_handle(
+                self.devh.as_ptr(),
+            )))
+        };
+        if err == Error::Success {
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+
+    /// Speed the device is connected with
+    #[must_use]
+    pub fn usb_speed(&self) -> UsbSpeed {
+        let speed = unsafe {
+            libusb_get_device_speed(libusb_get_device(uvc_get_libusb_handle(
+                self.devh.as_ptr(),
+            )))
+        };
+        match speed as libusb_speed {
+            uvc_sys::libusb_speed_LIBUSB_SPEED_LOW => UsbSpeed::Low,
+            uvc_sys::libusb_speed_LIBUSB_SPEED_FULL => UsbSpeed::Full,
+            uvc_sys::libusb_speed_LIBUSB_SPEED_HIGH => UsbSpeed::High,
+            uvc_sys::libusb_speed_LIBUSB_SPEED_SUPER => UsbSpeed::Super,
+            uvc_sys::libusb_speed_LIBUSB_SPEED_SUPER_PLUS => UsbSpeed::SuperPlus,
+            _ => UsbSpeed::Unknown,
+        }
+    }
+
+    /// Creates a stream handle bound to the given streaming interface
+    ///
+    /// The format and frame indices are looked up in the descriptors of
+    /// `interface_number` itself, as they can differ between interfaces.
+    /// Returns `Error::InvalidMode` if that interface doesn't offer the
+    /// format.
+    pub fn get_stream_handle_with_format_on_interface(
+        &'a self,
+        format: StreamFormat,
+        interface_number: u8,
+    ) -> Result<StreamHandle<'a>> {
+        let found = self.with_streaming_interfaces(|interfaces| {
+            let altsetting = interfaces
+                .iter()
+                .find(|altsetting| altsetting.bInterfaceNumber == interface_number)?;
+            find_stream_format(extra_descriptors(altsetting), format)
+        })?;
+        let (format_index, frame_index, interval) = found.ok_or(Error::InvalidMode)?;
+
+        unsafe {
+            // What uvc_get_stream_ctrl_format_size() fills in before probing
+            let mut handle: uvc_stream_ctrl_t = std::mem::zeroed();
+            handle.bmHint = 1 << 0; // dwFrameInterval is fixed
+            handle.bFormatIndex = format_index;
+            handle.bFrameIndex = frame_index;
+            handle.dwFrameInterval = interval;
+            handle.bInterfaceNumber = interface_number;
+            let err = uvc_probe_stream_ctrl(self.devh.as_ptr(), &mut handle).into();
+            if err == Error::Success {
+                Ok(StreamHandle { handle, devh: self })
+            } else {
+                Err(err)
+            }
+        }
+    }
+}
+
+impl<'a> Drop for DeviceHandle<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            uvc_close(self.devh.as_ptr());
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Describes the device
+pub struct DeviceDescription {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub bcd_uvc: u16,
+    pub serial_number: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+}
+
+unsafe impl<'a> Send for FormatDescriptor<'a> {}
+unsafe impl<'a> Sync for FormatDescriptor<'a> {}
+/// Describes possible formats
+pub struct FormatDescriptor<'a> {
+    format_desc: NonNull<uvc_format_desc_t>,
+    _ph: PhantomData<&'a uvc_format_desc_t>,
+}
+
+#[derive(Debug, PartialEq)]
+/// Describes what frame or format is supported
+pub enum DescriptionSubtype {
+    Undefined,
+    InputHeader,
+    OutputHeader,
+    StillImageFrame,
+    FormatUncompressed,
+    FrameUncompressed,
+    FormatMJPEG,
+    FrameMJPEG,
+    FormatMPEG2TS,
+    FormatDV,
+    ColorFormat,
+    FormatFrameBased,
+    FrameFrameBased,
+    FormatStreamBased,
+}
+
+impl From<uvc_vs_desc_subtype> for DescriptionSubtype {
+    fn from(x: uvc_vs_desc_subtype) -> DescriptionSubtype {
+        #[allow(non_upper_case_globals)]
+        match x {
+            uvc_vs_desc_subtype_UVC_VS_UNDEFINED => DescriptionSubtype::Undefined,
+            uvc_vs_desc_subtype_UVC_VS_INPUT_HEADER => DescriptionSubtype::InputHeader,
+            uvc_vs_desc_subtype_UVC_VS_OUTPUT_HEADER => DescriptionSubtype::OutputHeader,
+            uvc_vs_desc_subtype_UVC_VS_STILL_IMAGE_FRAME => DescriptionSubtype::StillImageFrame,
+            uvc_vs_desc_subtype_UVC_VS_FORMAT_UNCOMPRESSED => {
+                DescriptionSubtype::FormatUncompressed
+            }
+            uvc_vs_desc_subtype_UVC_VS_FRAME_UNCOMPRESSED => DescriptionSubtype::FrameUncompressed,
+            uvc_vs_desc_subtype_UVC_VS_FORMAT_MJPEG => DescriptionSubtype::FormatMJPEG,
+            uvc_vs_desc_subtype_UVC_VS_FRAME_MJPEG => DescriptionSubtype::FrameMJPEG,
+            uvc_vs_desc_subtype_UVC_VS_FORMAT_MPEG2TS => DescriptionSubtype::FormatMPEG2TS,
+            uvc_vs_desc_subtype_UVC_VS_FORMAT_DV => DescriptionSubtype::FormatDV,
+            uvc_vs_desc_subtype_UVC_VS_COLORFORMAT => DescriptionSubtype::ColorFormat,
+            uvc_vs_desc_subtype_UVC_VS_FORMAT_FRAME_BASED => DescriptionSubtype::FormatFrameBased,
+            uvc_vs_desc_subtype_UVC_VS_FRAME_FRAME_BASED => DescriptionSubtype::FrameFrameBased,
+            uvc_vs_desc_subtype_UVC_VS_FORMAT_STREAM_BASED => DescriptionSubtype::FormatStreamBased,
+            _ => DescriptionSubtype::Undefined,
+        }
+    }
+}
+
+impl<'a> FormatDescriptor<'a> {
+    #[must_use]
+    pub fn supported_formats(&self) -> FrameDescriptors<'_> {
+        FrameDescriptors {
+            head: unsafe { (*self.format_desc.as_ptr()).frame_descs },
+            _ph: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub fn subtype(&self) -> DescriptionSubtype {
+        unsafe { (*self.format_desc.as_ptr()).bDescriptorSubtype }.into()
+    }
+}
+
+unsafe impl<'a> Send for FormatDescriptors<'a> {}
+unsafe impl<'a> Sync for FormatDescriptors<'a> {}
+/// Iterate to get a `FormatDescriptor`
+pub struct FormatDescriptors<'a> {
+    head: *const uvc_format_desc_t,
+    _ph: PhantomData<&'a uvc_format_desc_t>,
+}
+
+impl<'a> Iterator for FormatDescriptors<'a> {
+    type Item = FormatDescriptor<'a>;
+
+    fn next(&mut self) -> Option<FormatDescriptor<'a>> {
+        match NonNull::new(self.head as *mut _) {
+            None => None,
+            Some(x) => {
+                let current = FormatDescriptor {
+                    format_desc: x,
+                    _ph: PhantomData,
+                };
+                self.head = unsafe { (*self.head).next };
+                Some(current)
+            }
+        }
+    }
+}
+
+unsafe impl<'a> Send for FrameDescriptor<'a> {}
+unsafe impl<'a> Sync for FrameDescriptor<'a> {}
+#[derive(Debug)]
+/// Describes possible frames
+pub struct FrameDescriptor<'a> {
+    frame_desc: NonNull<uvc_frame_desc_t>,
+    _ph: PhantomData<&'a uvc_frame_desc_t>,
+}
+
+impl<'a> FrameDescriptor<'a> {
+    #[must_use]
+    pub fn width(&self) -> u16 {
+        unsafe { (*self.frame_desc.as_ptr()).wWidth }
+    }
+    #[must_use]
+    pub fn height(&self) -> u16 {
+        unsafe { (*self.frame_desc.as_ptr()).wHeight }
+    }
+    /// Type of frame
+    #[must_use]
+    pub fn subtype(&self) -> DescriptionSubtype {
+        unsafe { (*self.frame_desc.as_ptr()).bDescriptorSubtype }.into()
+    }
+    /// Time in 100ns
+    #[must_use]
+    pub fn intervals(&self) -> &[u32] {
+        unsafe {
+            let intervals: *const u32 = (*self.frame_desc.as_ptr()).intervals;
+            if intervals.is_null() {
+                return &[];
+            }
+            let mut len = 0;
+            loop {
+                let x = *intervals.add(len);
+                if x == 0 {
+                    return slice::from_raw_parts::<'a>(intervals, len);
+                }
+                len += 1;
+            }
+        }
+    }
+
+    /// Duration between captures
+    #[must_use]
+    pub fn intervals_duration(&self) -> Vec<Duration> {
+        let times = self.intervals();
+        let mut durations = Vec::with_capacity(times.len());
+
+        for i in times {
+            durations.push(Duration::from_nanos(u64::from(*i) * 100));
+        }
+
+        durations
+    }
+}
+
+unsafe impl<'a> Send for FrameDescriptors<'a> {}
+unsafe impl<'a> Sync for FrameDescriptors<'a> {}
+/// Iterate to get a `FrameDescriptor`
+pub struct FrameDescriptors<'a> {
+    head: *mut uvc_frame_desc_t,
+    _ph: PhantomData<&'a uvc_frame_desc_t>,
+}
+
+impl<'a> Iterator for FrameDescriptors<'a> {
+    type Item = FrameDescriptor<'a>;
+
+    fn next(&mut self) -> Option<FrameDescriptor<'a>> {
+        match NonNull::new(self.head) {
+            None => None,
+            Some(x) => {
+                let current = FrameDescriptor {
+                    frame_desc: x,
+                    _ph: PhantomData,
+                };
+                unsafe { self.head = (*self.head).next };
+                Some(current)
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// Parser for the file named by the config-file property. It takes one
+// `key = value` per line like a GKeyFile, `#` and `;` start a comment and
+// entries may be grouped under a `[bigeyesrc]` header. Keys are property
+// names, except for
+//
+//   serial = SERIAL            binds the element to the camera with this serial
+//   xu.UNIT.SELECTOR = HEX..   extension unit control written on every open
+
+use std::fmt;
+
+// The group entries may be placed under
+const GROUP: &str = "bigeyesrc";
+
+// A property value, still to be checked against the property
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub line: usize,
+    pub key: String,
+    pub value: String,
+}
+
+// An extension unit control to write to the camera
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Control {
+    pub line: usize,
+    pub unit: u8,
+    pub selector: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub serial: Option<String>,
+    pub properties: Vec<Entry>,
+    pub controls: Vec<Control>,
+}
+
+// A line that was skipped and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// Parses as much of the file as possible, every line that can't be used is
+// reported as a problem. A key given twice takes its last value.
+pub fn parse(text: &str) -> (Config, Vec<Problem>) {
+    let mut config = Config::default();
+    let mut problems = Vec::new();
+    let mut in_other_group = false;
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let mut problem = |message: String| {
+            problems.push(Problem {
+                line: line_number,
+                message,
+            })
+        };
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(group) = line.strip_prefix('[') {
+            match group.strip_suffix(']').map(str::trim) {
+                Some(GROUP) => in_other_group = false,
+                Some(group) => {
+                    problem(format!("Unknown group [{}], its entries are ignored", group));
+                    in_other_group = true;
+                }
+                None => problem(format!("Unterminated group header {:?}", line)),
+            }
+            continue;
+        }
+        if in_other_group {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            problem(format!("Expected key = value, got {:?}", line));
+            continue;
+        };
+        let key = key.trim();
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        if key.is_empty() {
+            problem(format!("Missing key before = in {:?}", line));
+            continue;
+        }
+
+        if key == "serial" {
+            if value.is_empty() {
+                problem("Empty serial number".to_string());
+            } else {
+                config.serial = Some(value.to_string());
+            }
+        } else if let Some(address) = key.strip_prefix("xu.") {
+            match parse_control(address, value) {
+                Ok((unit, selector, data)) => {
+                    config.controls.retain(|c| (c.unit, c.selector) != (unit, selector));
+                    config.controls.push(Control {
+                        line: line_number,
+                        unit,
+                        selector,
+                        data,
+                    });
+                }
+                Err(message) => problem(format!("{}: {}", key, message)),
+            }
+        } else {
+            config.properties.retain(|entry| entry.key != key);
+            config.properties.push(Entry {
+                line: line_number,
+                key: key.to_string(),
+                value: value.to_string(),
+            });
+        }
+    }
+
+    (config, problems)
+}
+
+// Parses `UNIT.SELECTOR` and a value of whitespace separated hex bytes
+fn parse_control(address: &str, value: &str) -> Result<(u8, u8, Vec<u8>), String> {
+    let (unit, selector) = address
+        .split_once('.')
+        .ok_or_else(|| "Expected xu.UNIT.SELECTOR".to_string())?;
+    let unit = unit
+        .parse()
+        .map_err(|_| format!("Unit {:?} is not a number from 0 to 255", unit))?;
+    let selector = selector
+        .parse()
+        .map_err(|_| format!("Selector {:?} is not a number from 0 to 255", selector))?;
+
+    let data = value
+        .split_whitespace()
+        .map(|byte| {
+            let digits = byte.strip_prefix("0x").unwrap_or(byte);
+            u8::from_str_radix(digits, 16).map_err(|_| format!("{:?} is not a hex byte", byte))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if data.is_empty() {
+        return Err("No data bytes".to_string());
+    }
+    Ok((unit, selector, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(problems: &[Problem]) -> Vec<usize> {
+        problems.iter().map(|problem| problem.line).collect()
+    }
+
+    #[test]
+    fn missing_equals_and_empty_key_are_skipped() {
+        let (config, problems) = parse("invert\n = true\nmono = true\n");
+        assert_eq!(lines(&problems), [1, 2]);
+        assert!(problems[0].message.starts_with("Expected key = value"), "{}", problems[0]);
+        assert!(problems[1].message.starts_with("Missing key"), "{}", problems[1]);
+        assert_eq!(config.properties.len(), 1);
+        assert_eq!(config.properties[0].key, "mono");
+        assert_eq!(config.properties[0].line, 3);
+    }
+
+    #[test]
+    fn unknown_and_unterminated_groups() {
+        let text = "[other]\ninvert = true\n[bigeyesrc\nmono = true\n[bigeyesrc]\nbatch = true\n";
+        let (config, problems) = parse(text);
+        assert_eq!(lines(&problems), [1, 3]);
+        assert!(problems[0].message.starts_with("Unknown group [other]"), "{}", problems[0]);
+        assert!(problems[1].message.starts_with("Unterminated group header"), "{}", problems[1]);
+        // The unterminated header doesn't leave the unknown group
+        let keys = config.properties.iter().map(|entry| entry.key.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys, ["batch"]);
+    }
+
+    #[test]
+    fn bad_extension_unit_controls() {
+        let text = "xu.3 = 01\nxu.256.1 = 01\nxu.3.x = 01\nxu.3.1 = 0x1g\nxu.3.2 =\nxu.3.3 = 0x01 ff\n";
+        let (config, problems) = parse(text);
+        assert_eq!(lines(&problems), [1, 2, 3, 4, 5]);
+        assert!(problems[0].message.contains("Expected xu.UNIT.SELECTOR"), "{}", problems[0]);
+        assert!(problems[1].message.contains("Unit \"256\""), "{}", problems[1]);
+        assert!(problems[2].message.contains("Selector \"x\""), "{}", problems[2]);
+        assert!(problems[3].message.contains("\"0x1g\" is not a hex byte"), "{}", problems[3]);
+        assert!(problems[4].message.contains("No data bytes"), "{}", problems[4]);
+        assert_eq!(
+            config.controls,
+            [Control {
+                line: 6,
+                unit: 3,
+                selector: 3,
+                data: vec![0x01, 0xff],
+            }]
+        );
+    }
+
+    #[test]
+    fn last_value_of_a_key_wins() {
+        let text = "invert = true\nserial = A\nxu.3.1 = 01\ninvert = false\nserial = \"B\"\nxu.3.1 = 02\n";
+        let (config, problems) = parse(text);
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(config.serial.as_deref(), Some("B"));
+        assert_eq!(
+            config.properties,
+            [Entry {
+                line: 4,
+                key: "invert".to_string(),
+                value: "false".to_string(),
+            }]
+        );
+        assert_eq!(config.controls.len(), 1);
+        assert_eq!((config.controls[0].line, config.controls[0].data.as_slice()), (6, &[0x02][..]));
+    }
+}
@@ -10,15 +10,99 @@ use gst_base::prelude::*;
 use gst_base::subclass::base_src::CreateSuccess;
 use gst_base::subclass::prelude::*;
 
-use std::sync::{Arc, Mutex};
-use std::sync::LazyLock;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock, mpsc};
+use std::sync::{LazyLock, OnceLock};
 
-use uvc;
+use super::config;
+use super::process;
+#[cfg(target_os = "linux")]
+use super::sysfs;
+use super::{Format, FreezeAction, IlluminatorMode, PtsBase, StallAction};
+use crate::capture::{self, Camera};
+#[cfg(feature = "mock")]
+use crate::capture::TestPattern;
+use crate::meta::{self, FrameMeta, MultiviewModeTag};
 
 const WIDTH: i32 = 800;
 const HEIGHT: i32 = 400;
 const FRAMES_SECOND: i32 = 90;
 
+// Upper bound of frames kept between two create() calls
+const MAX_QUEUED_FRAMES: usize = 8;
+
+// Largest size offered for software scaling with strict-resolution=false
+const MAX_SCALED_SIZE: i32 = 4096;
+
+// Attempts to find the camera again after a USB port reset, and the delay
+// between them while it re-enumerates
+const PORT_RESET_ATTEMPTS: u32 = 10;
+const PORT_RESET_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+// Frames averaged for the brightness before measure-latency toggles the
+// illuminator, and how long it waits for the change afterwards
+const LATENCY_BASELINE_FRAMES: usize = 10;
+const LATENCY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+// How long self-test captures, and how far the measured frame rate may be
+// from the camera's before it fails
+const SELF_TEST_DURATION: std::time::Duration = std::time::Duration::from_secs(1);
+const SELF_TEST_FPS_TOLERANCE: f64 = 0.1;
+
+// How long leaving PLAYING waits for drain-on-stop to push the queued
+// frames, and for create() to take the next one before giving up, e.g.
+// when the push blocks on a sink that was paused first
+const DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+const DRAIN_STALL: std::time::Duration = std::time::Duration::from_millis(100);
+
+// Weight of a new sample in the smoothed clock offset estimate
+const CLOCK_OFFSET_SMOOTHING: i64 = 16;
+
+const DEFAULT_INTERFACE: u32 = 0;
+const DEFAULT_SERIALIZE_META: bool = false;
+const DEFAULT_BATCH: bool = false;
+const DEFAULT_FORMAT: Format = Format::Mjpeg;
+const DEFAULT_INVERT: bool = false;
+const DEFAULT_NORMALIZE: bool = false;
+const DEFAULT_STALL_ACTION: StallAction = StallAction::Drop;
+const DEFAULT_GAP_THRESHOLD: u32 = 50;
+const DEFAULT_ENABLE_BRIGHTNESS_MONITOR: bool = false;
+const DEFAULT_BRIGHTNESS_INTERVAL: u32 = 10;
+const DEFAULT_BRIGHTNESS_THRESHOLD: f64 = 10.0;
+const DEFAULT_BRIGHTNESS_DURATION: u32 = 2000;
+const DEFAULT_FREEZE_DETECTION: bool = false;
+const DEFAULT_FREEZE_THRESHOLD: u32 = 10;
+const DEFAULT_FREEZE_ACTION: FreezeAction = FreezeAction::Warn;
+const DEFAULT_DISABLE_AUTOSUSPEND: bool = cfg!(target_os = "linux");
+const DEFAULT_STRICT_RESOLUTION: bool = false;
+const DEFAULT_MULTIVIEW: bool = true;
+const DEFAULT_MONO: bool = false;
+const DEFAULT_ANAGLYPH: bool = false;
+const DEFAULT_OUTPUT_ASPECT: gst::Fraction = gst::Fraction::from_integer(0);
+const DEFAULT_OUTPUT_FILL: u32 = 0;
+const DEFAULT_STARTUP_DELAY: u32 = 0;
+const DEFAULT_OPEN_RETRIES: u32 = 0;
+const DEFAULT_OPEN_RETRY_DELAY: u32 = 200;
+const DEFAULT_ILLUMINATOR_UNIT: u32 = 0;
+const DEFAULT_ILLUMINATOR_SELECTOR: u32 = 0;
+const DEFAULT_ILLUMINATOR_MODE_SELECTOR: u32 = 0;
+const DEFAULT_ILLUMINATOR_MODE: IlluminatorMode = IlluminatorMode::Default;
+const DEFAULT_EXPOSURE: u32 = 0;
+const DEFAULT_GAIN: i32 = -1;
+const DEFAULT_LOG_THROTTLE: u32 = 1000;
+const DEFAULT_MIN_FRAME_SIZE: u32 = 1024;
+const DEFAULT_DEDUP: bool = false;
+const DEFAULT_DRAIN_ON_STOP: bool = false;
+const DEFAULT_CHECKSUM: bool = false;
+const DEFAULT_LOW_LATENCY: bool = false;
+const DEFAULT_AFFINITY_MASK: u64 = 0;
+const DEFAULT_AE_PRIORITY: bool = false;
+const DEFAULT_AUTO_DEGRADE: bool = false;
+const DEFAULT_PTS_BASE: PtsBase = PtsBase::RunningTime;
+#[cfg(feature = "mock")]
+const DEFAULT_TEST_PATTERN: TestPattern = TestPattern::EyeMarkers;
+
 static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
     gst::DebugCategory::new(
         "bigeyesrc",
@@ -27,44 +111,3880 @@ static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
     )
 });
 
-// Stream-specific state
+// Extra guidance appended to device lookup/open errors, and the details to
+// post them with in place of a plain bigeyesrc-error structure
+#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+fn access_hint(error: &capture::Error, serial: Option<&str>) -> (String, Option<gst::Structure>) {
+    match error.uvc_error() {
+        // On macOS the enumeration already fails with Access when the
+        // system UVC driver can't be detached from the device
+        #[cfg(target_os = "macos")]
+        Some(uvc::Error::Access) => (
+            ". macOS denied access to the camera: allow the application in System Settings > Privacy & Security > Camera, and run as root so libusb can detach the system UVC driver".to_string(),
+            None,
+        ),
+        // On Linux it's almost always a missing udev rule
+        #[cfg(target_os = "linux")]
+        Some(uvc::Error::Access) => match capture::access::diagnose(serial) {
+            Some(diagnosis) => {
+                let details = gst::Structure::builder("bigeyesrc-access")
+                    .field("node", diagnosis.node.display().to_string())
+                    .field("owner", &diagnosis.owner)
+                    .field("group", &diagnosis.group)
+                    .field("mode", diagnosis.mode & 0o7777)
+                    .field("in-plugdev", diagnosis.in_plugdev.unwrap_or(false))
+                    .field(
+                        "udev-rules",
+                        gst::Array::new(diagnosis.udev_rules.iter().map(|path| path.display().to_string())),
+                    )
+                    .field("suggestion", diagnosis.suggestion())
+                    .build();
+                (format!(". {}", diagnosis), Some(details))
+            }
+            None => (String::new(), None),
+        },
+        _ => (String::new(), None),
+    }
+}
+
+// Builds the details errors are posted with: the stage that failed, the
+// USB IDs of the camera and the libuvc error code if there is one
+fn error_details(stage: &str, error: Option<uvc::Error>) -> gst::Structure {
+    let mut details = gst::Structure::new_empty("bigeyesrc-error");
+    add_error_fields(&mut details, stage, error);
+    details
+}
+
+fn add_error_fields(details: &mut gst::StructureRef, stage: &str, error: Option<uvc::Error>) {
+    details.set("stage", stage);
+    details.set("vid", u32::from(capture::VENDOR_ID));
+    details.set("pid", u32::from(capture::PRODUCT_ID));
+    if let Some(error) = error {
+        details.set("uvc-error-code", error.code());
+    }
+}
+
+// An error along with the details to post it with, which a
+// gst::ErrorMessage can't carry
+#[derive(Debug, Clone)]
+struct Failure {
+    kind: gst::ResourceError,
+    debug: String,
+    details: gst::Structure,
+}
+
+impl Failure {
+    fn new(kind: gst::ResourceError, stage: &str, error: Option<uvc::Error>, debug: String) -> Self {
+        Failure {
+            kind,
+            debug,
+            details: error_details(stage, error),
+        }
+    }
+}
+
+// Pixel format of raw output
+fn raw_format(mono: bool) -> &'static str {
+    if mono { "GRAY8" } else { "YUY2" }
+}
+
+// Caps produced for the given output format
+fn format_caps(format: Format, mono: bool) -> gst::Caps {
+    let native = ((WIDTH as u32, HEIGHT as u32), vec![FRAMES_SECOND as u32]);
+    sized_caps(format, mono, [native].into_iter())
+}
+
+// Caps of the given output format with one structure per frame size, each
+// with its frame rates
+fn sized_caps(format: Format, mono: bool, sizes: impl Iterator<Item = ((u32, u32), Vec<u32>)>) -> gst::Caps {
+    sizes
+        .map(|((width, height), rates)| {
+            let builder = match format {
+                Format::Mjpeg | Format::Auto => gst::Structure::builder("image/jpeg"),
+                Format::Raw => gst::Structure::builder("video/x-raw").field("format", raw_format(mono)),
+            };
+            builder
+                .field("width", width as i32)
+                .field("height", height as i32)
+                .field("framerate", framerate(&rates))
+                .build()
+        })
+        .collect()
+}
+
+// The framerate field for whole frame rates, a list if there are several.
+// Fixating picks the first.
+fn framerate(rates: &[u32]) -> glib::SendValue {
+    match rates {
+        [fps] => gst::Fraction::new(*fps as i32, 1).to_send_value(),
+        rates => gst::List::new(rates.iter().map(|&fps| gst::Fraction::new(fps as i32, 1))).to_send_value(),
+    }
+}
+
+// FourCC of the camera modes an output format is captured in
+fn mode_fourcc(format: Format) -> [u8; 4] {
+    match format {
+        Format::Mjpeg | Format::Auto => *b"MJPG",
+        Format::Raw => *b"YUY2",
+    }
+}
+
+// Why `caps` aren't fixed, naming the first field without a single value,
+// or None if they are
+fn unfixed_reason(caps: &gst::CapsRef) -> Option<String> {
+    if caps.is_fixed() {
+        return None;
+    }
+    if caps.is_any() {
+        return Some("they are ANY".to_string());
+    }
+    if caps.size() != 1 {
+        return Some(format!("they have {} structures instead of one", caps.size()));
+    }
+    let s = caps.structure(0).unwrap();
+    let (field, value) = s.iter().find(|(_, value)| !value.is_fixed())?;
+    let value = value.serialize().map(String::from).unwrap_or_else(|_| format!("{:?}", value));
+    Some(format!("{} is {}", field, value))
+}
+
+// Name of a USB link speed as used in the stats
+fn usb_speed_name(speed: uvc::UsbSpeed) -> &'static str {
+    match speed {
+        uvc::UsbSpeed::Unknown => "unknown",
+        uvc::UsbSpeed::Low => "low",
+        uvc::UsbSpeed::Full => "full",
+        uvc::UsbSpeed::High => "high",
+        uvc::UsbSpeed::Super => "super",
+        uvc::UsbSpeed::SuperPlus => "super-plus",
+    }
+}
+
+// Bytes per second isochronous transfers can carry at a link speed, None if
+// it is plenty for any mode of the camera or not known
+fn usb_capacity(speed: uvc::UsbSpeed) -> Option<u64> {
+    match speed {
+        // No isochronous transfers at all
+        uvc::UsbSpeed::Low => Some(0),
+        // One 1023 byte packet per 1 ms frame
+        uvc::UsbSpeed::Full => Some(1_023_000),
+        // Three 1024 byte packets per 125 us microframe
+        uvc::UsbSpeed::High => Some(24_576_000),
+        uvc::UsbSpeed::Super | uvc::UsbSpeed::SuperPlus | uvc::UsbSpeed::Unknown => None,
+    }
+}
+
+// Bytes per second a mode needs on the USB link
+fn needed_bandwidth(format: Format, (width, height): (u32, u32), fps: u32) -> u64 {
+    let raw_rate = u64::from(width) * u64::from(height) * 2 * u64::from(fps);
+    match format {
+        Format::Raw => raw_rate,
+        // Frames of the camera compress to around a tenth of the raw size
+        Format::Mjpeg | Format::Auto => raw_rate / 10,
+    }
+}
+
+// Whether starting the stream failed because the USB link has no bandwidth
+// left for the mode: libuvc finds no alternate setting large enough, or
+// the isochronous transfers can't be submitted
+fn is_bandwidth_error(e: &capture::Error) -> bool {
+    matches!(
+        e,
+        capture::Error::Stream(uvc::Error::InvalidMode | uvc::Error::NoMem | uvc::Error::IO)
+    )
+}
+
+// Summary returned by the probe-device signal
+fn probe_structure(probe: &capture::Probe, streaming: bool) -> gst::Structure {
+    let modes = probe.modes.iter().map(|mode| {
+        gst::Structure::builder("mode")
+            .field("fourcc", String::from_utf8_lossy(&mode.fourcc).as_ref())
+            .field("width", mode.width)
+            .field("height", mode.height)
+            .field("fps", mode.fps)
+            .build()
+    });
+    gst::Structure::builder("bigeye-device")
+        .field("serial", probe.serial.as_deref())
+        .field("manufacturer", probe.manufacturer.as_deref())
+        .field("product", probe.product.as_deref())
+        .field(
+            "uvc-version",
+            format!("{:x}.{:02x}", probe.uvc_version >> 8, probe.uvc_version & 0xff),
+        )
+        .field("bus", u32::from(probe.usb_address.0))
+        .field("address", u32::from(probe.usb_address.1))
+        .field_if_some("usb-speed", probe.usb_speed.map(usb_speed_name))
+        .field("modes", gst::Array::new(modes))
+        .field("controls", gst::Array::new(probe.controls.iter().copied()))
+        .field("streaming", streaming)
+        .build()
+}
+
+// Whether a numeric value lies within the limits of its property. Other
+// types are already checked when they are deserialized.
+fn in_range(pspec: &glib::ParamSpec, value: &glib::Value) -> bool {
+    fn within<T: PartialOrd + for<'a> glib::value::FromValue<'a>>(value: &glib::Value, min: T, max: T) -> bool {
+        value.get::<T>().is_ok_and(|v| min <= v && v <= max)
+    }
+
+    if let Some(p) = pspec.downcast_ref::<glib::ParamSpecUInt>() {
+        within(value, p.minimum(), p.maximum())
+    } else if let Some(p) = pspec.downcast_ref::<glib::ParamSpecInt>() {
+        within(value, p.minimum(), p.maximum())
+    } else if let Some(p) = pspec.downcast_ref::<glib::ParamSpecUInt64>() {
+        within(value, p.minimum(), p.maximum())
+    } else if let Some(p) = pspec.downcast_ref::<glib::ParamSpecInt64>() {
+        within(value, p.minimum(), p.maximum())
+    } else if let Some(p) = pspec.downcast_ref::<glib::ParamSpecDouble>() {
+        within(value, p.minimum(), p.maximum())
+    } else {
+        true
+    }
+}
+
+// The size closest to `wanted`, weighing relative area and aspect ratio
+// differences equally
+fn nearest_size(sizes: &[(u32, u32)], wanted: (u32, u32)) -> Option<(u32, u32)> {
+    let distance = |(width, height): (u32, u32)| {
+        let area = (width as f64 * height as f64) / (wanted.0 as f64 * wanted.1 as f64);
+        let aspect = (width as f64 / height as f64) / (wanted.0 as f64 / wanted.1 as f64);
+        area.ln().abs() + aspect.ln().abs()
+    };
+    sizes
+        .iter()
+        .copied()
+        .filter(|&(width, height)| width > 0 && height > 0)
+        .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+}
+
+// The smallest size of `aspect` a picture of `size` fits into, by adding
+// bars above and below or at the sides
+fn padded_size((width, height): (u32, u32), (numer, denom): (u32, u32)) -> (u32, u32) {
+    let (width, height, numer, denom) = (width as u64, height as u64, numer as u64, denom as u64);
+    if width * denom > height * numer {
+        (width as u32, (width * denom).div_ceil(numer) as u32)
+    } else {
+        ((height * numer).div_ceil(denom) as u32, height as u32)
+    }
+}
+
+// Size of the anaglyph made from a frame of `size`, see
+// process::anaglyph_yuy2()
+fn anaglyph_size((width, height): (u32, u32)) -> (u32, u32) {
+    (width / 4 * 2, height)
+}
+
+// The largest size with the aspect ratio of `size` that fits into `bounds`
+fn fitted_size((width, height): (u32, u32), bounds: (u32, u32)) -> (u32, u32) {
+    let (width, height) = (width as u64, height as u64);
+    let (max_width, max_height) = (bounds.0 as u64, bounds.1 as u64);
+    if max_width * height > max_height * width {
+        ((max_height * width / height) as u32, bounds.1)
+    } else {
+        (bounds.0, (max_width * height / width) as u32)
+    }
+}
+
+// What to write to the units with an exposure or gain control, the left
+// eye's value to the first unit and the right eye's to the second. A
+// per-eye value that isn't set falls back to the shared one. With a single
+// unit the shared value, or else a per-eye one, applies to both eyes, and
+// the flag is set if that loses a difference between them.
+fn eye_values<T: Copy + PartialEq>(
+    (shared, left, right): (Option<T>, Option<T>, Option<T>),
+    units: &[u8],
+) -> (Vec<(u8, T)>, bool) {
+    let (left, right) = (left.or(shared), right.or(shared));
+    match *units {
+        [] => (Vec::new(), false),
+        [unit] => {
+            let value = shared.or(left).or(right);
+            (value.map(|value| (unit, value)).into_iter().collect(), left != right)
+        }
+        [left_unit, right_unit, ..] => {
+            let values = left.map(|value| (left_unit, value)).into_iter().chain(right.map(|value| (right_unit, value)));
+            (values.collect(), false)
+        }
+    }
+}
+
+// Layout of raw output buffers, and the metas downstream accepts to
+// describe it
+#[derive(Debug, Clone, Copy)]
+struct RawOutput {
+    format: gst_video::VideoFormat,
+    width: u32,
+    height: u32,
+    stride: usize,
+    video_meta: bool,
+    crop_meta: bool,
+}
+
+// Property value storage
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    interface: u32,
+    serialize_meta: bool,
+    batch: bool,
+    format: Format,
+    invert: bool,
+    normalize: bool,
+    stall_action: StallAction,
+    gap_threshold: u32,
+    enable_brightness_monitor: bool,
+    brightness_interval: u32,
+    brightness_threshold: f64,
+    brightness_duration: u32,
+    freeze_detection: bool,
+    freeze_threshold: u32,
+    freeze_action: FreezeAction,
+    disable_autosuspend: bool,
+    strict_resolution: bool,
+    multiview: bool,
+    mono: bool,
+    anaglyph: bool,
+    output_aspect: gst::Fraction,
+    output_fill: u32,
+    startup_delay: u32,
+    open_retries: u32,
+    open_retry_delay: u32,
+    illuminator_unit: u32,
+    illuminator_selector: u32,
+    illuminator_mode_selector: u32,
+    illuminator_mode: IlluminatorMode,
+    exposure: u32,
+    left_exposure: u32,
+    right_exposure: u32,
+    gain: i32,
+    left_gain: i32,
+    right_gain: i32,
+    log_throttle: u32,
+    min_frame_size: u32,
+    dedup: bool,
+    drain_on_stop: bool,
+    checksum: bool,
+    low_latency: bool,
+    affinity_mask: u64,
+    ae_priority: bool,
+    auto_degrade: bool,
+    pts_base: PtsBase,
+    #[cfg(feature = "mock")]
+    test_pattern: TestPattern,
+}
+
+impl Settings {
+    // Aspect ratio output-aspect pads frames of `format` to, if any
+    fn output_aspect(&self, format: Format) -> Option<(u32, u32)> {
+        let aspect = self.output_aspect;
+        (format == Format::Raw && aspect.numer() > 0).then(|| (aspect.numer() as u32, aspect.denom() as u32))
+    }
+
+    // Whether raw frames of `format` are turned into an anaglyph, which
+    // needs the color mono drops
+    fn anaglyph(&self, format: Format) -> bool {
+        self.anaglyph && format == Format::Raw && !self.mono
+    }
+
+    // Size before any scaling of the output made from frames captured at
+    // `size`, after anaglyph and output-aspect
+    fn output_size(&self, format: Format, size: (u32, u32)) -> (u32, u32) {
+        let size = if self.anaglyph(format) { anaglyph_size(size) } else { size };
+        match self.output_aspect(format) {
+            Some(aspect) => padded_size(size, aspect),
+            None => size,
+        }
+    }
+
+    // Copies the settings of START_TIME_PROPERTIES from the ones streaming
+    // started with. All others apply immediately.
+    fn with_start_time_from(mut self, started: &Settings) -> Settings {
+        for (_, copy) in START_TIME_PROPERTIES {
+            copy(&mut self, started);
+        }
+        self
+    }
+}
+
+// Copies one setting from the second settings into the first
+type CopySetting = fn(&mut Settings, &Settings);
+
+// Properties that only take effect in start(), with how to copy their
+// setting from the ones streaming started with. Their paramspecs are
+// flagged mutable in READY, all other writable properties apply
+// immediately. set_property() warns about changes to these while
+// streaming.
+const START_TIME_PROPERTIES: &[(&str, CopySetting)] = &[
+    ("interface", |s, started| s.interface = started.interface),
+    ("serialize-meta", |s, started| s.serialize_meta = started.serialize_meta),
+    ("format", |s, started| s.format = started.format),
+    ("disable-autosuspend", |s, started| s.disable_autosuspend = started.disable_autosuspend),
+    ("strict-resolution", |s, started| s.strict_resolution = started.strict_resolution),
+    ("multiview", |s, started| s.multiview = started.multiview),
+    ("mono", |s, started| s.mono = started.mono),
+    ("anaglyph", |s, started| s.anaglyph = started.anaglyph),
+    ("output-aspect", |s, started| s.output_aspect = started.output_aspect),
+    ("startup-delay", |s, started| s.startup_delay = started.startup_delay),
+    ("open-retries", |s, started| s.open_retries = started.open_retries),
+    ("open-retry-delay", |s, started| s.open_retry_delay = started.open_retry_delay),
+    ("low-latency", |s, started| s.low_latency = started.low_latency),
+    ("affinity-mask", |s, started| s.affinity_mask = started.affinity_mask),
+    ("ae-priority", |s, started| s.ae_priority = started.ae_priority),
+    ("auto-degrade", |s, started| s.auto_degrade = started.auto_degrade),
+    ("pts-base", |s, started| s.pts_base = started.pts_base),
+    // Read when going to READY, it has no setting of its own
+    ("config-file", |_, _| {}),
+    #[cfg(feature = "mock")]
+    ("test-pattern", |s, started| s.test_pattern = started.test_pattern),
+];
+
+// Whether the property is one of START_TIME_PROPERTIES
+fn applies_at_start(name: &str) -> bool {
+    START_TIME_PROPERTIES.iter().any(|&(property, _)| property == name)
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            interface: DEFAULT_INTERFACE,
+            serialize_meta: DEFAULT_SERIALIZE_META,
+            batch: DEFAULT_BATCH,
+            format: DEFAULT_FORMAT,
+            invert: DEFAULT_INVERT,
+            normalize: DEFAULT_NORMALIZE,
+            stall_action: DEFAULT_STALL_ACTION,
+            gap_threshold: DEFAULT_GAP_THRESHOLD,
+            enable_brightness_monitor: DEFAULT_ENABLE_BRIGHTNESS_MONITOR,
+            brightness_interval: DEFAULT_BRIGHTNESS_INTERVAL,
+            brightness_threshold: DEFAULT_BRIGHTNESS_THRESHOLD,
+            brightness_duration: DEFAULT_BRIGHTNESS_DURATION,
+            freeze_detection: DEFAULT_FREEZE_DETECTION,
+            freeze_threshold: DEFAULT_FREEZE_THRESHOLD,
+            freeze_action: DEFAULT_FREEZE_ACTION,
+            disable_autosuspend: DEFAULT_DISABLE_AUTOSUSPEND,
+            strict_resolution: DEFAULT_STRICT_RESOLUTION,
+            multiview: DEFAULT_MULTIVIEW,
+            mono: DEFAULT_MONO,
+            anaglyph: DEFAULT_ANAGLYPH,
+            output_aspect: DEFAULT_OUTPUT_ASPECT,
+            output_fill: DEFAULT_OUTPUT_FILL,
+            startup_delay: DEFAULT_STARTUP_DELAY,
+            open_retries: DEFAULT_OPEN_RETRIES,
+            open_retry_delay: DEFAULT_OPEN_RETRY_DELAY,
+            illuminator_unit: DEFAULT_ILLUMINATOR_UNIT,
+            illuminator_selector: DEFAULT_ILLUMINATOR_SELECTOR,
+            illuminator_mode_selector: DEFAULT_ILLUMINATOR_MODE_SELECTOR,
+            illuminator_mode: DEFAULT_ILLUMINATOR_MODE,
+            exposure: DEFAULT_EXPOSURE,
+            left_exposure: DEFAULT_EXPOSURE,
+            right_exposure: DEFAULT_EXPOSURE,
+            gain: DEFAULT_GAIN,
+            left_gain: DEFAULT_GAIN,
+            right_gain: DEFAULT_GAIN,
+            log_throttle: DEFAULT_LOG_THROTTLE,
+            min_frame_size: DEFAULT_MIN_FRAME_SIZE,
+            dedup: DEFAULT_DEDUP,
+            drain_on_stop: DEFAULT_DRAIN_ON_STOP,
+            checksum: DEFAULT_CHECKSUM,
+            low_latency: DEFAULT_LOW_LATENCY,
+            affinity_mask: DEFAULT_AFFINITY_MASK,
+            ae_priority: DEFAULT_AE_PRIORITY,
+            auto_degrade: DEFAULT_AUTO_DEGRADE,
+            pts_base: DEFAULT_PTS_BASE,
+            #[cfg(feature = "mock")]
+            test_pattern: DEFAULT_TEST_PATTERN,
+        }
+    }
+}
+
+// A frame as handed over by the camera
+struct CapturedFrame {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    meta: FrameMeta,
+}
+
+impl From<capture::Frame> for CapturedFrame {
+    fn from(frame: capture::Frame) -> Self {
+        let meta = FrameMeta {
+            sequence: frame.sequence(),
+            capture_time: gst::ClockTime::from_nseconds(frame.capture_time().as_nanos() as u64),
+            repeat: false,
+            brightness: None,
+            checksum: None,
+        };
+        CapturedFrame {
+            width: frame.width(),
+            height: frame.height(),
+            data: frame.into_bytes(),
+            meta,
+        }
+    }
+}
+
+// Streaming counters, exposed through the "stats" property
+#[derive(Debug, Default, Clone, Copy)]
+struct Stats {
+    // Buffers pushed downstream, including repeats
+    delivered: u64,
+    // Frames replaced by newer ones before they could be pushed
+    dropped: u64,
+    // Most frames waiting in the queue at once
+    high_water_mark: u64,
+    // Frames that failed validation
+    corrupt: u64,
+    // Frames smaller than min-frame-size
+    runt_frames: u64,
+    // Frames skipped by dedup for repeating the last sequence number
+    duplicates: u64,
+    // Buffers whose PTS was moved past that of the previous one
+    pts_corrections: u64,
+    // Buffers that repeat the last good frame
+    repeated: u64,
+    // GAP events pushed in place of a frame
+    gaps: u64,
+    // Most recent mean luma of the left and right eye
+    brightness: Option<(f64, f64)>,
+    // Frames identical to the one before them
+    frozen: u64,
+    // Times more than freeze-threshold identical frames arrived in a row
+    freezes: u64,
+    // Whether USB autosuspend of the open camera is turned off
+    autosuspend_disabled: bool,
+    // Link speed of the open camera
+    usb_speed: Option<&'static str>,
+}
+
+impl Stats {
+    fn to_structure(self) -> gst::Structure {
+        gst::Structure::builder("application/x-bigeyesrc-stats")
+            .field("delivered", self.delivered)
+            .field("dropped", self.dropped)
+            .field("high-water-mark", self.high_water_mark)
+            .field("corrupt", self.corrupt)
+            .field("runt-frames", self.runt_frames)
+            .field("duplicates", self.duplicates)
+            .field("pts-corrections", self.pts_corrections)
+            .field("repeated", self.repeated)
+            .field("gaps", self.gaps)
+            .field_if_some("brightness-left", self.brightness.map(|(left, _)| left))
+            .field_if_some("brightness-right", self.brightness.map(|(_, right)| right))
+            .field("frozen", self.frozen)
+            .field("freezes", self.freezes)
+            .field("autosuspend-disabled", self.autosuspend_disabled)
+            .field_if_some("usb-speed", self.usb_speed)
+            .build()
+    }
+}
+
+// Rate limit for one kind of repetitive warning
+#[derive(Debug, Default)]
+struct Throttle {
+    last: Option<std::time::Instant>,
+    // Occurrences since the last warning
+    pending: u64,
+}
+
+impl Throttle {
+    // Records `count` occurrences. Returns how many happened since the last
+    // warning if one may be logged now.
+    fn hit(&mut self, count: u64, interval: std::time::Duration) -> Option<u64> {
+        self.pending += count;
+        if self.last.is_some_and(|last| last.elapsed() < interval) {
+            return None;
+        }
+        self.last = Some(std::time::Instant::now());
+        Some(std::mem::take(&mut self.pending))
+    }
+}
+
+// Frozen frame detection progress
+#[derive(Debug, Default)]
+struct FreezeState {
+    last_hash: Option<u64>,
+    // Consecutive frames identical to the first one of the run
+    identical: u32,
+    // Whether the current run was already reported
+    reported: bool,
+}
+
+// Device state
 // #[allow(dead_code)]
 struct State {
-    info: Option<gst_video::VideoInfo>,
-    stream: Option<uvc::ActiveStream<'static, Arc<Mutex<Option<Vec<u8>>>>>>,
-    latest_frame: Arc<Mutex<Option<Vec<u8>>>>,
+    camera: Option<Camera>,
+    // Frame sizes of the configured format listed by the device, and the
+    // size and frame rate it is streaming at
+    sizes: Vec<(u32, u32)>,
+    capture_size: (u32, u32),
+    capture_fps: u32,
+    // Every mode the camera lists, for the frame rates offered per size
+    modes: Vec<capture::Mode>,
+    // Whether format=auto picked the format in start(), downstream may then
+    // still negotiate the other one
+    auto_format: bool,
+    // Serial number of the opened camera, so reopening finds the same one
+    serial: Option<String>,
+    // Set while the camera is opened, closed or probed without the lock
+    // held, see unlocked_device_io()
+    device_busy: bool,
 }
 
 impl Default for State {
     fn default() -> State {
         State {
+            camera: None,
+            sizes: Vec::new(),
+            modes: Vec::new(),
+            capture_size: (WIDTH as u32, HEIGHT as u32),
+            capture_fps: FRAMES_SECOND as u32,
+            auto_format: false,
+            serial: None,
+            device_busy: false,
+        }
+    }
+}
+
+// What create() reads and updates for every frame. Kept apart from the
+// device state and only locked for short steps, so property access and
+// device I/O don't hold up the frames. Locked after the state.
+struct Stream {
+    info: Option<gst_video::VideoInfo>,
+    // Whether downstream listed VideoMeta and VideoCropMeta in the
+    // allocation query
+    video_meta: bool,
+    crop_meta: bool,
+    // Settings at start(), while started
+    started_settings: Option<Settings>,
+    // Cleared by stop-capture, which closes the camera while the element
+    // keeps running
+    capturing: bool,
+    // Mark the next buffer as a discontinuity after capture restarted
+    discont: bool,
+    // Counters, frames dropped by the open camera's queue are added when
+    // it is closed
+    stats: Stats,
+    // Last good buffer, retained for stall-action=repeat-last
+    last_buffer: Option<gst::Buffer>,
+    // Since when the left/right eye has been below brightness-threshold,
+    // and whether that has been warned about already
+    dark_since: [Option<std::time::Instant>; 2],
+    dark_warned: [bool; 2],
+    // Smoothed offset in nanoseconds of the pipeline clock relative to the
+    // frame capture timestamps
+    clock_offset: Option<i64>,
+    // Running time of the first timestamp since start(), subtracted from
+    // all of them with pts-base=zero
+    first_pts: Option<gst::ClockTime>,
+    // Whether create() returned a buffer since start(). BaseSrc pushes the
+    // segment after the first create(), serialized events must not go
+    // ahead of it.
+    buffer_pushed: bool,
+    freeze: FreezeState,
+    // Warnings about corrupt frames, dropped frames and stalls
+    corrupt_log: Throttle,
+    drop_log: Throttle,
+    stall_log: Throttle,
+    // Whether a runt frame was warned about since start()
+    runt_warned: bool,
+    // Whether create() pinned the streaming thread to affinity-mask
+    thread_pinned: bool,
+    // Sequence number of the last frame accepted for pushing, for dedup
+    last_sequence: Option<u32>,
+    // PTS of the last buffer pushed, and whether a correction of one was
+    // warned about since start()
+    last_pts: Option<gst::ClockTime>,
+    pts_warned: bool,
+    // Wall-clock time of the last stall, kept across restarts
+    last_timeout: Option<gst::ClockTime>,
+    // Receives the capture time and mean luma of each frame while
+    // measure-latency runs
+    latency_probe: Option<mpsc::Sender<(gst::ClockTime, f64)>>,
+    // Frames create() still has to push for drain-on-stop, Some(0) once it
+    // took the last one
+    drain_remaining: Option<usize>,
+    // Set when an EOS started the drain, create() ends the stream after it
+    drain_eos: bool,
+}
+
+impl Stream {
+    // Duration of a frame at the negotiated frame rate, None until caps are
+    // set or for a variable frame rate
+    fn frame_duration(&self) -> Option<gst::ClockTime> {
+        let fps = self.info.as_ref()?.fps();
+        if fps.numer() <= 0 {
+            return None;
+        }
+        gst::ClockTime::SECOND.mul_div_floor(fps.denom() as u64, fps.numer() as u64)
+    }
+
+    // Timestamp for a buffer or GAP at the given running time, counted from
+    // the first one with pts-base=zero
+    fn timestamp(&mut self, pts_base: PtsBase, running_time: Option<gst::ClockTime>) -> Option<gst::ClockTime> {
+        let running_time = running_time?;
+        match pts_base {
+            PtsBase::RunningTime => Some(running_time),
+            PtsBase::Zero => Some(running_time.saturating_sub(*self.first_pts.get_or_insert(running_time))),
+        }
+    }
+
+    // `settings` with the start-time ones kept at their values from start()
+    // while started
+    fn effective(&self, settings: Settings) -> Settings {
+        match self.started_settings {
+            Some(ref started) => settings.with_start_time_from(started),
+            None => settings,
+        }
+    }
+}
+
+impl Default for Stream {
+    fn default() -> Stream {
+        Stream {
             info: None,
-            stream: None,
-            latest_frame: Arc::new(Mutex::new(None)),
+            video_meta: false,
+            crop_meta: false,
+            started_settings: None,
+            capturing: true,
+            discont: false,
+            stats: Stats::default(),
+            last_buffer: None,
+            dark_since: [None; 2],
+            dark_warned: [false; 2],
+            clock_offset: None,
+            first_pts: None,
+            buffer_pushed: false,
+            freeze: FreezeState::default(),
+            corrupt_log: Throttle::default(),
+            drop_log: Throttle::default(),
+            stall_log: Throttle::default(),
+            runt_warned: false,
+            thread_pinned: false,
+            last_sequence: None,
+            last_pts: None,
+            pts_warned: false,
+            last_timeout: None,
+            latency_probe: None,
+            drain_remaining: None,
+            drain_eos: false,
+        }
+    }
+}
+
+// The config-file property and what was loaded from it
+#[derive(Default)]
+struct ConfigState {
+    file: Option<String>,
+    // Properties set on the element, the file doesn't override them
+    explicit: HashSet<String>,
+    // While the file is applied its values don't count as explicit
+    applying: bool,
+    serial: Option<String>,
+    controls: Vec<config::Control>,
+}
+
+// Struct containing all the element data
+#[derive(Default)]
+pub struct BigEyeSrc {
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+    stream: Mutex<Stream>,
+    config: Mutex<ConfigState>,
+    // Frames of whichever camera is open, read by create() without the
+    // state lock
+    queue: OnceLock<capture::FrameQueue>,
+    // Set while drain-on-stop runs, so create() only looks at the drain in
+    // the stream then
+    draining: AtomicBool,
+    // Set while BaseSrc wants create() to return
+    flushing: AtomicBool,
+    // Signalled by unlock() to end waits on the state
+    wakeup: Condvar,
+    // Signalled by create() for each frame drain-on-stop takes, and by
+    // unlock(). Waited on with the stream lock.
+    drained: Condvar,
+    // Sysfs power/control attribute changed for disable-autosuspend on the
+    // open camera and the value to restore when it is closed. Locked after
+    // the state.
+    #[cfg(target_os = "linux")]
+    autosuspend_restore: Mutex<Option<(std::path::PathBuf, String)>>,
+    // Installed with super::set_frame_hook()
+    frame_hook: RwLock<Option<Arc<super::FrameHook>>>,
+}
+
+impl BigEyeSrc {
+    // The queue every camera of this element delivers into
+    fn queue(&self) -> &capture::FrameQueue {
+        self.queue.get_or_init(|| capture::FrameQueue::new(MAX_QUEUED_FRAMES))
+    }
+
+    pub(super) fn set_frame_hook(&self, hook: Option<Arc<super::FrameHook>>) {
+        gst::debug!(CAT, imp = self, "{} frame hook", if hook.is_some() { "Installing" } else { "Removing" });
+        *self.frame_hook.write().unwrap() = hook;
+    }
+
+    // Posts a failure on the bus as an error message with its details
+    fn post_failure(&self, err: Failure) {
+        gst::element_imp_error!(self, err.kind, ["{}", err.debug], details: err.details);
+    }
+
+    // The settings currently in effect. Start-time settings changed while
+    // streaming are held back until the next start().
+    fn effective_settings(&self) -> Settings {
+        let settings = *self.settings.lock().unwrap();
+        self.stream.lock().unwrap().effective(settings)
+    }
+
+    // Frame rates the camera lists in `format` at `size`, or at any size
+    // without one. The current rate comes first, then the faster ones.
+    // Without a listed mode that is the only one.
+    fn frame_rates(state: &State, format: Format, size: Option<(u32, u32)>) -> Vec<u32> {
+        let mut rates = state
+            .modes
+            .iter()
+            .filter(|mode| mode.fourcc == mode_fourcc(format))
+            .filter(|mode| size.is_none_or(|size| (mode.width, mode.height) == size))
+            .map(|mode| mode.fps)
+            .collect::<Vec<_>>();
+        rates.sort_by_key(|&fps| (fps != state.capture_fps, std::cmp::Reverse(fps)));
+        rates.dedup();
+        if rates.is_empty() {
+            rates.push(state.capture_fps);
+        }
+        rates
+    }
+
+    // Caps offered for one output format
+    fn output_caps(&self, format: Format, settings: &Settings, state: &State) -> gst::Caps {
+        // Prefer the size the device is already streaming at
+        let sizes = if state.sizes.is_empty() {
+            vec![(WIDTH as u32, HEIGHT as u32)]
+        } else {
+            let current = state.capture_size;
+            let others = state.sizes.iter().copied().filter(|&size| size != current);
+            state.sizes.contains(&current).then_some(current).into_iter().chain(others).collect()
+        };
+
+        let scaled = format == Format::Raw && !settings.strict_resolution;
+        let scaled_caps = || {
+            gst::Caps::builder("video/x-raw")
+                .field("format", raw_format(settings.mono))
+                .field("width", gst::IntRange::new(1, MAX_SCALED_SIZE))
+                .field("height", gst::IntRange::new(1, MAX_SCALED_SIZE))
+                .field("framerate", framerate(&Self::frame_rates(state, format, None)))
+                .build()
+        };
+        let rates = |size| Self::frame_rates(state, format, Some(size));
+        let mut caps = if settings.anaglyph(format) || settings.output_aspect(format).is_some() {
+            // The device sizes made into an anaglyph or padded to the
+            // aspect come first, scaled output fits the picture into
+            // whatever size is negotiated
+            let mut caps = sized_caps(
+                format,
+                settings.mono,
+                sizes.iter().map(|&size| (settings.output_size(format, size), rates(size))),
+            );
+            if scaled {
+                caps.merge(scaled_caps());
+            }
+            caps
+        } else if scaled {
+            scaled_caps()
+        } else {
+            sized_caps(format, settings.mono, sizes.into_iter().map(|size| (size, rates(size))))
+        };
+
+        // Both eyes side by side, left first, which are the default flags.
+        // An anaglyph holds them on top of each other.
+        if format == Format::Raw && settings.multiview && !settings.anaglyph(format) {
+            let mode = gst_video::VideoMultiviewMode::SideBySide.to_caps_string().unwrap();
+            for s in caps.make_mut().iter_mut() {
+                s.set("multiview-mode", mode.as_str());
+            }
+        }
+        caps
+    }
+
+    // Reads config-file and sets the properties it lists, except for those
+    // set on the element. Lines that can't be applied only cause warnings.
+    fn load_config_file(&self) -> Result<(), Failure> {
+        let Some(path) = self.config.lock().unwrap().file.clone() else {
+            return Ok(());
+        };
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            Failure::new(
+                gst::ResourceError::OpenRead,
+                "config",
+                None,
+                format!("Could not read config file {}: {}", path, e),
+            )
+        })?;
+
+        let (file, mut problems) = config::parse(&text);
+        let explicit = {
+            let mut config = self.config.lock().unwrap();
+            config.applying = true;
+            config.explicit.clone()
+        };
+        let obj = self.obj();
+        for entry in &file.properties {
+            let mut problem = |message: String| {
+                problems.push(config::Problem {
+                    line: entry.line,
+                    message,
+                })
+            };
+            let pspec = match obj.find_property(&entry.key) {
+                Some(pspec) if entry.key == "config-file" => {
+                    problem(format!("{} can't be set from the file", pspec.name()));
+                    continue;
+                }
+                Some(pspec) if pspec.flags().contains(glib::ParamFlags::WRITABLE) => pspec,
+                Some(pspec) => {
+                    problem(format!("{} is read-only", pspec.name()));
+                    continue;
+                }
+                None => {
+                    problem(format!("Unknown key {}", entry.key));
+                    continue;
+                }
+            };
+            if explicit.contains(pspec.name()) {
+                gst::debug!(
+                    CAT,
+                    imp = self,
+                    "{} was set on the element, ignoring line {} of {}",
+                    pspec.name(),
+                    entry.line,
+                    path
+                );
+                continue;
+            }
+            match glib::Value::deserialize_with_pspec(&entry.value, &pspec) {
+                Ok(value) if in_range(&pspec, &value) => obj.set_property_from_value(pspec.name(), &value),
+                _ => problem(format!("Invalid value {:?} for {}", entry.value, pspec.name())),
+            }
+        }
+
+        let mut config = self.config.lock().unwrap();
+        config.applying = false;
+        config.serial = file.serial;
+        config.controls = file.controls;
+        drop(config);
+
+        problems.sort_by_key(|problem| problem.line);
+        for problem in &problems {
+            gst::element_imp_warning!(
+                self,
+                gst::ResourceError::Settings,
+                ("Ignored a line of the config file"),
+                ["{} {}", path, problem]
+            );
+        }
+        gst::info!(CAT, imp = self, "Applied config file {} with {} problems", path, problems.len());
+        Ok(())
+    }
+
+    // Opens the camera for streaming, called by change_state() on the way
+    // to PAUSED instead of from start(). BaseSrc posts the error start()
+    // returns without details, and one of its own after it, while a failure
+    // here is posted once with its details.
+    fn start_camera(&self) -> Result<(), Failure> {
+        gst::info!(CAT, imp = self, "Starting video capture");
+
+        let mut settings = *self.settings.lock().unwrap();
+        // A previous stop leaves it set, BaseSrc only clears it after start()
+        self.flushing.store(false, Ordering::SeqCst);
+
+        // A self-test or probe still running has the camera open
+        let mut state = self.wait_for_device(self.state.lock().unwrap());
+        if settings.startup_delay > 0 {
+            gst::debug!(CAT, imp = self, "Waiting {} ms before opening the camera", settings.startup_delay);
+            let delay = std::time::Duration::from_millis(settings.startup_delay.into());
+            state = self.wakeup.wait_timeout_while(state, delay, |_| !self.flushing.load(Ordering::SeqCst)).unwrap().0;
+        }
+
+        let serial = self.config.lock().unwrap().serial.clone();
+        state.auto_format = settings.format == Format::Auto;
+        if state.auto_format {
+            settings.format = self.pick_format(serial.as_deref());
+        }
+        if settings.format != Format::Raw && (settings.invert || settings.normalize) {
+            gst::warning!(CAT, imp = self, "invert and normalize only apply to the raw format");
+        }
+        if settings.format != Format::Raw && settings.mono {
+            gst::warning!(CAT, imp = self, "mono only applies to the raw format");
+        }
+        if settings.anaglyph && settings.format != Format::Raw {
+            gst::warning!(CAT, imp = self, "anaglyph only applies to the raw format");
+        } else if settings.anaglyph && settings.mono {
+            gst::warning!(CAT, imp = self, "anaglyph needs color, it is ignored with mono");
+        }
+        if settings.format != Format::Raw && settings.output_aspect.numer() > 0 {
+            gst::warning!(CAT, imp = self, "output-aspect only applies to the raw format");
+        }
+        if settings.format != Format::Raw && settings.enable_brightness_monitor {
+            gst::warning!(CAT, imp = self, "The brightness monitor only works with the raw format");
+        }
+        {
+            let mut stream = self.stream.lock().unwrap();
+            stream.capturing = true;
+            stream.discont = false;
+            stream.clock_offset = None;
+            stream.first_pts = None;
+            stream.buffer_pushed = false;
+            stream.dark_since = [None; 2];
+            stream.dark_warned = [false; 2];
+            stream.freeze = FreezeState::default();
+            stream.stats = Stats::default();
+            stream.runt_warned = false;
+            stream.thread_pinned = false;
+            stream.last_pts = None;
+            stream.pts_warned = false;
+        }
+        self.queue().clear();
+        // low-latency leaves a single slot the callback overwrites
+        self.queue().set_capacity(if settings.low_latency { 1 } else { MAX_QUEUED_FRAMES });
+
+        // BaseSrc only negotiates caps after start(), so the camera streams
+        // in its native mode until set_caps() switches it to the caps
+        state.capture_size = (WIDTH as u32, HEIGHT as u32);
+        state.capture_fps = FRAMES_SECOND as u32;
+        let mut attempt = 0;
+        let mut retry_delay = std::time::Duration::from_millis(settings.open_retry_delay.into());
+        let (camera, capture_size, capture_fps) = loop {
+            match self.open_camera(&settings, state.capture_size, state.capture_fps, serial.as_deref()) {
+                Ok(opened) => break opened,
+                // Settings the camera doesn't support won't work on a retry either
+                Err(err) if attempt < settings.open_retries && err.kind != gst::ResourceError::Settings => {
+                    attempt += 1;
+                    gst::warning!(
+                        CAT,
+                        imp = self,
+                        "Could not open the camera, retry {} of {} in {} ms: {}",
+                        attempt,
+                        settings.open_retries,
+                        retry_delay.as_millis(),
+                        err.debug
+                    );
+                    let flushing = || self.flushing.load(Ordering::SeqCst);
+                    state = self.wakeup.wait_timeout_while(state, retry_delay, |_| !flushing()).unwrap().0;
+                    if flushing() {
+                        return Err(err);
+                    }
+                    retry_delay = retry_delay.saturating_mul(2);
+                }
+                Err(err) => return Err(err),
+            }
+        };
+        if attempt > 0 {
+            gst::info!(CAT, imp = self, "Opened the camera on retry {}", attempt);
+        }
+        state.capture_size = capture_size;
+        state.capture_fps = capture_fps;
+        state.serial = camera.serial().map(String::from);
+        gst::info!(CAT, imp = self, "Camera serial number {:?}", state.serial);
+        state.sizes = camera.sizes().to_vec();
+        state.modes = camera.probe().map_or_else(Vec::new, |probe| probe.modes);
+        state.camera = Some(camera);
+        self.stream.lock().unwrap().started_settings = Some(settings);
+
+        drop(state);
+
+        gst::info!(CAT, imp = self, "Started video capture");
+        Ok(())
+    }
+
+    // Opens the camera at the given size and starts streaming
+    fn open_camera(
+        &self,
+        settings: &Settings,
+        (width, height): (u32, u32),
+        fps: u32,
+        serial: Option<&str>,
+    ) -> Result<(Camera, (u32, u32), u32), Failure> {
+        let requested = ((width, height), fps);
+        let ((mut width, mut height), mut fps) = requested;
+        // Probed on the first bandwidth failure, lowest mode first
+        let mut lower_modes: Option<Vec<((u32, u32), u32)>> = None;
+        let camera = loop {
+            let selector = capture::Selector {
+                serial: serial.map(String::from),
+                interface: settings.interface,
+                format: match settings.format {
+                    Format::Mjpeg | Format::Auto => capture::PixelFormat::Mjpeg,
+                    Format::Raw => capture::PixelFormat::Yuy2,
+                },
+                width,
+                height,
+                fps,
+                queue_size: MAX_QUEUED_FRAMES,
+                affinity_mask: settings.affinity_mask,
+                #[cfg(feature = "mock")]
+                test_pattern: settings.test_pattern,
+            };
+
+            match Camera::open_with_queue(&selector, self.queue()) {
+                Ok(camera) => break camera,
+                Err(e) if settings.auto_degrade && is_bandwidth_error(&e) => {
+                    let modes = lower_modes
+                        .get_or_insert_with(|| self.lower_modes(settings.format, ((width, height), fps), serial));
+                    let Some(((lower_width, lower_height), lower_fps)) = modes.pop() else {
+                        gst::warning!(CAT, imp = self, "No lower mode left to try after {}x{}@{}", width, height, fps);
+                        return Err(self.open_failure(e, serial));
+                    };
+                    gst::warning!(
+                        CAT,
+                        imp = self,
+                        "Could not stream at {}x{}@{}, trying {}x{}@{}: {}",
+                        width,
+                        height,
+                        fps,
+                        lower_width,
+                        lower_height,
+                        lower_fps,
+                        e
+                    );
+                    ((width, height), fps) = ((lower_width, lower_height), lower_fps);
+                }
+                Err(e) => return Err(self.open_failure(e, serial)),
+            }
+        };
+
+        if ((width, height), fps) != requested {
+            gst::element_imp_warning!(
+                self,
+                gst::ResourceError::Settings,
+                ("The USB link is too slow for the requested mode, streaming at {}x{} and {} fps", width, height, fps),
+                ["auto-degrade lowered {}x{}@{} to {}x{}@{}", requested.0.0, requested.0.1, requested.1, width, height, fps]
+            );
+        }
+        gst::info!(
+            CAT,
+            imp = self,
+            "Stream started successfully at {}x{}@{} on streaming interface {}",
+            width,
+            height,
+            fps,
+            settings.interface
+        );
+        gst::debug!(CAT, imp = self, "Device frame sizes for {:?}: {:?}", settings.format, camera.sizes());
+        match camera.mode() {
+            Some(mode) if (mode.width, mode.height, mode.fps) != (width, height, fps) => {
+                gst::warning!(
+                    CAT,
+                    imp = self,
+                    "Camera negotiated {}x{}@{} instead of {}x{}@{}",
+                    mode.width,
+                    mode.height,
+                    mode.fps,
+                    width,
+                    height,
+                    fps
+                );
+            }
+            _ => {}
+        }
+        // Checked on every open, the camera may come back on another port
+        if let Some(speed) = camera.usb_speed() {
+            self.check_usb_speed(speed, settings.format, (width, height), fps);
+        }
+        let controls = self.config.lock().unwrap().controls.clone();
+        for control in &controls {
+            if let Err(e) = camera.set_extension_control(control.unit, control.selector, &control.data) {
+                gst::element_imp_warning!(
+                    self,
+                    gst::ResourceError::Settings,
+                    ("Could not apply a camera setting from the config file"),
+                    [
+                        "Writing unit {} selector {} from line {} failed: {}",
+                        control.unit,
+                        control.selector,
+                        control.line,
+                        e
+                    ]
+                );
+            }
+        }
+        self.apply_ae_priority(&camera, settings.ae_priority);
+        self.apply_illuminator_mode(&camera, settings);
+        self.apply_eye_controls(&camera, settings);
+        if settings.disable_autosuspend
+            && let Some(address) = camera.usb_address()
+        {
+            self.disable_autosuspend(address);
+        }
+        gst::info!(CAT, imp = self, "Stream started, waiting for frames");
+
+        Ok((camera, (width, height), fps))
+    }
+
+    // Why the camera couldn't be opened, with the access hint if the device
+    // node is the problem
+    fn open_failure(&self, e: capture::Error, serial: Option<&str>) -> Failure {
+        let (hint, details) = access_hint(&e, serial);
+        let mut details = details.unwrap_or_else(|| gst::Structure::new_empty("bigeyesrc-error"));
+        add_error_fields(&mut details, e.stage(), e.uvc_error());
+        let kind = match e {
+            capture::Error::NotFound(_) => gst::ResourceError::NotFound,
+            capture::Error::NoInterface { .. } | capture::Error::Format(_) => gst::ResourceError::Settings,
+            _ => gst::ResourceError::OpenRead,
+        };
+        Failure {
+            kind,
+            debug: format!("{}{}", e, hint),
+            details,
+        }
+    }
+
+    // Modes of the format the camera lists that need less bandwidth than
+    // the given one, lowest first so the next one to try can be popped
+    fn lower_modes(&self, format: Format, (size, fps): ((u32, u32), u32), serial: Option<&str>) -> Vec<((u32, u32), u32)> {
+        let probe = match capture::probe(serial) {
+            Ok(probe) => probe,
+            Err(e) => {
+                gst::warning!(CAT, imp = self, "Could not list the modes of the camera for auto-degrade: {}", e);
+                return Vec::new();
+            }
+        };
+        let fourcc = mode_fourcc(format);
+        let needed = needed_bandwidth(format, size, fps);
+        let mut modes = probe
+            .modes
+            .iter()
+            .filter(|mode| mode.fourcc == fourcc)
+            .map(|mode| ((mode.width, mode.height), mode.fps))
+            .filter(|&(size, fps)| needed_bandwidth(format, size, fps) < needed)
+            .collect::<Vec<_>>();
+        // Among modes of equal bandwidth the larger picture is tried first
+        modes.sort_by_key(|&(size, fps)| (needed_bandwidth(format, size, fps), size.0 * size.1));
+        modes.dedup();
+        gst::debug!(CAT, imp = self, "Lower modes for auto-degrade: {:?}", modes);
+        modes
+    }
+
+    // Writes illuminator-mode into the first byte of its extension unit
+    // control, keeping the length and any other bytes the camera reports.
+    // Nothing is written for the default mode.
+    fn apply_illuminator_mode(&self, camera: &Camera, settings: &Settings) {
+        let value = match settings.illuminator_mode {
+            IlluminatorMode::Default => return,
+            IlluminatorMode::Flood => 0,
+            IlluminatorMode::Pattern => 1,
+        };
+        if settings.illuminator_unit == 0 || settings.illuminator_mode_selector == 0 {
+            gst::element_imp_warning!(
+                self,
+                gst::ResourceError::Settings,
+                ("illuminator-mode is set, but its control is not known"),
+                ["Set illuminator-unit and illuminator-mode-selector to the extension unit control of the illuminator mode"]
+            );
+            return;
+        }
+
+        let (unit, selector) = (settings.illuminator_unit as u8, settings.illuminator_mode_selector as u8);
+        let result = camera.extension_control(unit, selector).and_then(|mut data| {
+            if data.is_empty() {
+                return Err(uvc::Error::InvalidParam);
+            }
+            data[0] = value;
+            camera.set_extension_control(unit, selector, &data)
+        });
+        match result {
+            Ok(()) => gst::debug!(CAT, imp = self, "Set the illuminator to {:?}", settings.illuminator_mode),
+            Err(e) => gst::element_imp_warning!(
+                self,
+                gst::ResourceError::Settings,
+                ("The camera has no illuminator mode control at {}:{}", unit, selector),
+                ["Setting illuminator-mode to {:?} failed: {} ({:?})", settings.illuminator_mode, e, e]
+            ),
+        }
+    }
+
+    // Applies a changed illuminator-mode to the open camera, the next open
+    // applies it otherwise
+    fn update_illuminator_mode(&self) {
+        let settings = self.effective_settings();
+        let state = self.wait_for_device(self.state.lock().unwrap());
+        if let Some(camera) = state.camera.as_ref() {
+            self.apply_illuminator_mode(camera, &settings);
+        }
+    }
+
+    // Writes exposure and gain, per eye on cameras with a control unit for
+    // each sensor. The unit with the lower ID is taken to be the left eye's.
+    fn apply_eye_controls(&self, camera: &Camera, settings: &Settings) {
+        let exposure = |value: u32| (value != 0).then_some(value);
+        let gain = |value: i32| u16::try_from(value).ok();
+        self.apply_eye_control(
+            "exposure",
+            camera.exposure_units(),
+            (exposure(settings.exposure), exposure(settings.left_exposure), exposure(settings.right_exposure)),
+            |unit, value| camera.set_exposure(unit, value),
+        );
+        self.apply_eye_control(
+            "gain",
+            camera.gain_units(),
+            (gain(settings.gain), gain(settings.left_gain), gain(settings.right_gain)),
+            |unit, value| camera.set_gain(unit, value),
+        );
+    }
+
+    fn apply_eye_control<T: Copy + PartialEq + std::fmt::Display>(
+        &self,
+        name: &str,
+        units: Result<Vec<u8>, uvc::Error>,
+        values: (Option<T>, Option<T>, Option<T>),
+        set: impl Fn(u8, T) -> Result<(), uvc::Error>,
+    ) {
+        if values == (None, None, None) {
+            return;
+        }
+        let mut units = match units {
+            Ok(units) if !units.is_empty() => units,
+            result => {
+                gst::element_imp_warning!(
+                    self,
+                    gst::ResourceError::Settings,
+                    ("The camera has no {} control", name),
+                    ["Listing the {} controls failed: {:?}", name, result.err()]
+                );
+                return;
+            }
+        };
+        units.sort_unstable();
+
+        let (values, shared) = eye_values(values, &units);
+        if shared {
+            gst::element_imp_warning!(
+                self,
+                gst::ResourceError::Settings,
+                ("The camera has one {} control for both eyes, left-{} and right-{} can't differ", name, name, name),
+                ["Setting {} of unit {} for both eyes", name, units[0]]
+            );
+        }
+        for (unit, value) in values {
+            match set(unit, value) {
+                Ok(()) => gst::debug!(CAT, imp = self, "Set {} of unit {} to {}", name, unit, value),
+                Err(e) => gst::element_imp_warning!(
+                    self,
+                    gst::ResourceError::Settings,
+                    ("Could not set {} on the camera", name),
+                    ["Setting {} of unit {} to {} failed: {}", name, unit, value, e]
+                ),
+            }
+        }
+    }
+
+    // Applies changed exposure or gain settings to the open camera, the
+    // next open applies them otherwise
+    fn update_eye_controls(&self) {
+        let settings = self.effective_settings();
+        let state = self.wait_for_device(self.state.lock().unwrap());
+        if let Some(camera) = state.camera.as_ref() {
+            self.apply_eye_controls(camera, &settings);
+        }
+    }
+
+    // The AE priority control only means something while auto-exposure is
+    // on, with manual exposure the frame rate is fixed anyway
+    fn apply_ae_priority(&self, camera: &Camera, variable: bool) {
+        match camera.ae_mode() {
+            Ok(uvc::AutoExposureMode::Auto | uvc::AutoExposureMode::AperturePriority) => {}
+            Ok(mode) => {
+                gst::debug!(CAT, imp = self, "Auto-exposure is {:?}, not setting ae-priority", mode);
+                return;
+            }
+            Err(e) => {
+                gst::debug!(CAT, imp = self, "Could not read the auto-exposure mode, not setting ae-priority: {}", e);
+                return;
+            }
+        }
+
+        let priority = if variable {
+            uvc::AutoExposurePriority::Variable
+        } else {
+            uvc::AutoExposurePriority::Constant
+        };
+        match camera.set_ae_priority(priority) {
+            Ok(()) => gst::debug!(CAT, imp = self, "Set the auto-exposure priority to {:?}", priority),
+            Err(e) => gst::element_imp_warning!(
+                self,
+                gst::ResourceError::Settings,
+                ("The camera does not support setting ae-priority"),
+                ["Setting the auto-exposure priority to {:?} failed: {}", priority, e]
+            ),
+        }
+    }
+
+    // Turns USB autosuspend of the camera off through sysfs, remembering the
+    // previous setting for close_camera(). Failures only result in a warning.
+    #[cfg(target_os = "linux")]
+    fn disable_autosuspend(&self, (bus, address): (u8, u8)) {
+        let result = sysfs::usb_device_path(bus, address)
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))
+            .and_then(|path| sysfs::set_power_control(&path, "on").map(|previous| (path, previous)));
+
+        match result {
+            Ok((path, previous)) => {
+                gst::info!(CAT, imp = self, "Disabled autosuspend of {} (was {})", path.display(), previous);
+                *self.autosuspend_restore.lock().unwrap() = Some((path, previous));
+            }
+            Err(err) => {
+                gst::element_imp_warning!(
+                    self,
+                    gst::ResourceError::Settings,
+                    ("Could not disable USB autosuspend of the camera"),
+                    [
+                        "Writing power/control of bus {} device {} failed: {}. Streaming may stall periodically, a udev rule setting ATTR{{power/control}}=\"on\" avoids this",
+                        bus,
+                        address,
+                        err
+                    ]
+                );
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn disable_autosuspend(&self, _address: (u8, u8)) {
+        gst::warning!(CAT, imp = self, "disable-autosuspend is only supported on Linux");
+    }
+
+    // Gives the USB port of the camera back the power/control value it had
+    // before disable_autosuspend(). Done before the camera goes away, since
+    // a reopened or re-enumerated camera starts out with autosuspend on.
+    fn restore_autosuspend(&self) {
+        #[cfg(target_os = "linux")]
+        if let Some((path, previous)) = self.autosuspend_restore.lock().unwrap().take() {
+            match sysfs::set_power_control(&path, &previous) {
+                Ok(_) => gst::debug!(CAT, imp = self, "Restored {} power control to {}", path.display(), previous),
+                Err(err) => gst::warning!(CAT, imp = self, "Could not restore power control of {}: {}", path.display(), err),
+            }
+        }
+    }
+
+    // Closes the open camera, if any, and restores its autosuspend setting.
+    // Its dropped frames stay counted by the queue, the sequence numbers of
+    // the next one start over.
+    fn close_camera(&self, state: &mut State) {
+        self.restore_autosuspend();
+        state.camera = None;
+        self.stream.lock().unwrap().last_sequence = None;
+    }
+
+    // Warns when the camera is connected too slowly for the mode it streams
+    fn check_usb_speed(&self, speed: uvc::UsbSpeed, format: Format, (width, height): (u32, u32), fps: u32) {
+        gst::info!(CAT, imp = self, "Camera connected at {} speed", usb_speed_name(speed));
+        let Some(capacity) = usb_capacity(speed) else {
+            return;
+        };
+
+        let needed = needed_bandwidth(format, (width, height), fps);
+        if needed > capacity {
+            gst::element_imp_warning!(
+                self,
+                gst::ResourceError::Settings,
+                ("The camera is connected at USB {} speed, too slow for the selected mode", usb_speed_name(speed)),
+                [
+                    "{:?} at {}x{} and {} fps needs about {} bytes/s, the link carries at most {}. Expect dropped frames, connect the headset to a USB 3 port without adapters",
+                    format,
+                    width,
+                    height,
+                    fps,
+                    needed,
+                    capacity
+                ]
+            );
+        }
+    }
+
+    // What format=auto stands for: raw when the USB link of the camera can
+    // carry it in the native mode, MJPEG otherwise
+    #[cfg(not(feature = "mock"))]
+    fn pick_format(&self, serial: Option<&str>) -> Format {
+        let speed = match capture::probe(serial) {
+            Ok(probe) => probe.usb_speed,
+            Err(e) => {
+                gst::warning!(CAT, imp = self, "Could not read the USB speed of the camera: {}", e);
+                None
+            }
+        };
+        let format = match speed {
+            // Without a known speed MJPEG is the safe choice
+            None => Format::Mjpeg,
+            Some(speed) => match usb_capacity(speed) {
+                Some(capacity)
+                    if needed_bandwidth(Format::Raw, (WIDTH as u32, HEIGHT as u32), FRAMES_SECOND as u32)
+                        > capacity =>
+                {
+                    Format::Mjpeg
+                }
+                _ => Format::Raw,
+            },
+        };
+        gst::info!(
+            CAT,
+            imp = self,
+            "format=auto picked {:?} for a USB link at {} speed",
+            format,
+            speed.map_or("unknown", usb_speed_name)
+        );
+        format
+    }
+
+    // The mock backend only generates raw frames
+    #[cfg(feature = "mock")]
+    fn pick_format(&self, _serial: Option<&str>) -> Format {
+        gst::info!(CAT, imp = self, "format=auto picked Raw for the mock camera");
+        Format::Raw
+    }
+
+    // Waits until no other thread is opening, closing, restarting or probing
+    // the camera without the lock held
+    fn wait_for_device<'a>(&self, state: MutexGuard<'a, State>) -> MutexGuard<'a, State> {
+        self.wakeup.wait_while(state, |state| state.device_busy).unwrap()
+    }
+
+    // Runs `io` on the device with the state lock released, so the
+    // properties aren't blocked meanwhile. Other device I/O waits for it
+    // in wait_for_device(). Returns the lock again with the result.
+    fn unlocked_device_io<'a, R>(
+        &'a self,
+        state: MutexGuard<'a, State>,
+        io: impl FnOnce() -> R,
+    ) -> (MutexGuard<'a, State>, R) {
+        let mut state = self.wait_for_device(state);
+        state.device_busy = true;
+        drop(state);
+        let result = io();
+        let mut state = self.state.lock().unwrap();
+        state.device_busy = false;
+        self.wakeup.notify_all();
+        (state, result)
+    }
+
+    // Runs `io` on the open camera through unlocked_device_io(). The camera
+    // is taken out of the state meanwhile and put back afterwards. Returns
+    // None without an open camera.
+    fn camera_io<'a, R>(
+        &'a self,
+        state: MutexGuard<'a, State>,
+        io: impl FnOnce(&mut Camera) -> R,
+    ) -> (MutexGuard<'a, State>, Option<R>) {
+        let mut state = self.wait_for_device(state);
+        let Some(mut camera) = state.camera.take() else {
+            return (state, None);
+        };
+        let (mut state, result) = self.unlocked_device_io(state, || {
+            let result = io(&mut camera);
+            (camera, result)
+        });
+        let (camera, result) = result;
+        state.camera = Some(camera);
+        (state, Some(result))
+    }
+
+    // Closes the camera and opens it again, e.g. to recover from a stuck sensor
+    fn reset_device(&self) -> Result<(), Failure> {
+        gst::info!(CAT, imp = self, "Resetting device");
+
+        let settings = self.effective_settings();
+        let mut state = self.wait_for_device(self.state.lock().unwrap());
+
+        // The old handle has to be closed before the device can be opened
+        // again, both happen with the lock released
+        let old = state.camera.take();
+        self.close_camera(&mut state);
+        self.stream.lock().unwrap().freeze = FreezeState::default();
+
+        // If the camera is gone, e.g. because the headset was swapped, the
+        // first one found is opened instead, unless the config file binds
+        // the element to that camera
+        let bound = self.config.lock().unwrap().serial.is_some();
+        let (size, fps, previous) = (state.capture_size, state.capture_fps, state.serial.clone());
+        let (mut state, opened) = self.unlocked_device_io(state, || {
+            drop(old);
+            match self.open_camera(&settings, size, fps, previous.as_deref()) {
+                Err(err) if err.kind == gst::ResourceError::NotFound && previous.is_some() && !bound => {
+                    gst::info!(CAT, imp = self, "Camera {:?} not found, looking for any camera", previous);
+                    self.open_camera(&settings, size, fps, None)
+                }
+                result => result,
+            }
+        });
+        let (camera, capture_size, capture_fps) = opened?;
+        // auto-degrade may have picked a lower mode
+        state.capture_size = capture_size;
+        state.capture_fps = capture_fps;
+        state.sizes = camera.sizes().to_vec();
+        state.modes = camera.probe().map_or_else(Vec::new, |probe| probe.modes);
+        let serial = camera.serial().map(String::from);
+        state.serial = serial.clone();
+        state.camera = Some(camera);
+        drop(state);
+
+        // Cameras are told apart by serial number, without a previous one
+        // it's unknown whether this is another camera
+        if previous.is_some() && previous != serial {
+            gst::info!(CAT, imp = self, "Camera changed from {:?} to {:?}", previous, serial);
+            let structure = gst::Structure::builder("bigeye-device-changed")
+                .field("old-serial", previous)
+                .field("new-serial", serial)
+                .build();
+            let obj = self.obj();
+            let _ = obj.post_message(gst::message::Element::builder(structure).src(&*obj).build());
+        }
+
+        gst::info!(CAT, imp = self, "Device reset");
+        Ok(())
+    }
+
+    // Closes the camera while the element keeps running, create() meanwhile
+    // pushes GAP events at the frame rate
+    fn stop_capture(&self) -> Result<(), Failure> {
+        let mut state = self.wait_for_device(self.state.lock().unwrap());
+        {
+            let stream = self.stream.lock().unwrap();
+            if stream.started_settings.is_none() {
+                return Err(Failure::new(
+                    gst::ResourceError::Failed,
+                    "state",
+                    None,
+                    "Can't stop capturing, the element is not started".to_string(),
+                ));
+            }
+            if !stream.capturing {
+                return Ok(());
+            }
+        }
+
+        self.close_camera(&mut state);
+        let mut stream = self.stream.lock().unwrap();
+        stream.capturing = false;
+        // Darkness while not capturing is intentional
+        stream.dark_since = [None; 2];
+        stream.dark_warned = [false; 2];
+        drop(stream);
+        drop(state);
+
+        gst::info!(CAT, imp = self, "Stopped capturing");
+        self.obj().notify("capturing");
+        Ok(())
+    }
+
+    // Stops the stream and starts it again on the open camera, which is
+    // quicker than reopening it. The USB transfers run through camera_io()
+    // so the lock isn't held meanwhile, create() keeps pushing the frames
+    // queued before the restart. Returns false if the element isn't
+    // streaming, since there is nothing to restart then.
+    fn restart_stream(&self) -> Result<bool, Failure> {
+        let state = self.wait_for_device(self.state.lock().unwrap());
+        if self.flushing.load(Ordering::SeqCst) || !self.stream.lock().unwrap().capturing {
+            gst::warning!(CAT, imp = self, "Not streaming, there is no stream to restart");
+            return Ok(false);
+        }
+
+        gst::info!(CAT, imp = self, "Restarting the stream");
+        // The camera stays open if the restart fails, the next reset or stop
+        // closes it
+        let Some(restarted) = self.camera_io(state, Camera::restart_stream).1 else {
+            gst::warning!(CAT, imp = self, "Not streaming, there is no stream to restart");
+            return Ok(false);
+        };
+        restarted.map_err(|err| {
+            Failure::new(
+                gst::ResourceError::Failed,
+                err.stage(),
+                err.uvc_error(),
+                format!("Could not restart the stream: {}", err),
+            )
+        })?;
+        let mut stream = self.stream.lock().unwrap();
+        stream.discont = true;
+        stream.freeze = FreezeState::default();
+        stream.last_sequence = None;
+        Ok(true)
+    }
+
+    // Opens the camera again after stop-capture
+    fn start_capture(&self) -> Result<(), Failure> {
+        {
+            let stream = self.stream.lock().unwrap();
+            if stream.started_settings.is_none() {
+                return Err(Failure::new(
+                    gst::ResourceError::Failed,
+                    "state",
+                    None,
+                    "Can't start capturing, the element is not started".to_string(),
+                ));
+            }
+            if stream.capturing {
+                return Ok(());
+            }
+        }
+
+        self.reset_device()?;
+
+        let mut stream = self.stream.lock().unwrap();
+        stream.capturing = true;
+        stream.discont = true;
+        drop(stream);
+
+        gst::info!(CAT, imp = self, "Started capturing");
+        self.obj().notify("capturing");
+        Ok(())
+    }
+
+    // Pushes an event serialized with the buffers from create(). Before the
+    // first buffer BaseSrc hasn't pushed the segment yet, so the event is
+    // dropped rather than sent ahead of it. Returns whether it was pushed.
+    fn push_serialized_event(&self, event: gst::Event) -> bool {
+        if !self.stream.lock().unwrap().buffer_pushed {
+            gst::debug!(CAT, imp = self, "Not pushing {:?} event before the segment", event.type_());
+            return false;
+        }
+        self.obj().src_pad().push_event(event)
+    }
+
+    // Pushes a GAP event per frame duration for as long as capture is
+    // stopped. Returns whether it had to wait.
+    fn wait_for_capture(&self, duration: gst::ClockTime, pts_base: PtsBase) -> Result<bool, gst::FlowError> {
+        let mut waited = false;
+        loop {
+            if self.flushing.load(Ordering::SeqCst) {
+                return Err(gst::FlowError::Flushing);
+            }
+            let mut stream = self.stream.lock().unwrap();
+            if stream.capturing {
+                return Ok(waited);
+            }
+
+            let now = stream.timestamp(pts_base, self.running_time());
+            drop(stream);
+            if let Some(now) = now {
+                let gap = gst::event::Gap::builder(now).duration(duration).build();
+                self.push_serialized_event(gap);
+            }
+            waited = true;
+            std::thread::sleep(duration.into());
+        }
+    }
+
+    // Resets the USB port of the camera and opens it again, for when only a
+    // replug would recover it otherwise. The camera is found again by its
+    // serial number since it may re-enumerate with a new address.
+    fn reset_usb_port(&self) -> Result<(), Failure> {
+        let mut state = self.wait_for_device(self.state.lock().unwrap());
+        let Some(camera) = state.camera.take() else {
+            return Err(Failure::new(
+                gst::ResourceError::Failed,
+                "state",
+                None,
+                "Can't reset the camera, it is not open".to_string(),
+            ));
+        };
+        // The port reset puts autosuspend back to the default anyway
+        self.restore_autosuspend();
+        let (state, reset) = self.unlocked_device_io(state, || camera.reset_port());
+        drop(state);
+
+        match reset {
+            Ok(()) => gst::info!(CAT, imp = self, "USB port reset"),
+            Err(uvc::Error::NotFound) => {
+                gst::info!(CAT, imp = self, "USB port reset, the camera re-enumerates")
+            }
+            Err(e) => {
+                return Err(Failure::new(
+                    gst::ResourceError::Failed,
+                    "reset",
+                    Some(e),
+                    format!("Could not reset the USB port: {} ({:?})", e, e),
+                ));
+            }
+        }
+
+        let mut attempt = 1;
+        loop {
+            match self.reset_device() {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt < PORT_RESET_ATTEMPTS => {
+                    gst::debug!(CAT, imp = self, "Camera not back yet: {:?}", err);
+                }
+                Err(err) => return Err(err),
+            }
+            attempt += 1;
+            std::thread::sleep(PORT_RESET_DELAY);
+        }
+    }
+
+    // Toggles the illuminator and measures how long it takes until frames
+    // show the change in brightness
+    fn measure_latency(&self) -> Result<gst::Structure, String> {
+        let settings = self.effective_settings();
+        if settings.format != Format::Raw {
+            return Err("measure-latency needs format=raw to see the brightness".to_string());
+        }
+        if settings.illuminator_unit == 0 {
+            return Err("The illuminator control is not known, set illuminator-unit and illuminator-selector to its extension unit control".to_string());
+        }
+        let (unit, selector) = (settings.illuminator_unit as u8, settings.illuminator_selector as u8);
+
+        // The probe is claimed before the control is read, so a second
+        // measurement started meanwhile is turned down
+        let (sender, samples) = mpsc::channel();
+        {
+            let mut stream = self.stream.lock().unwrap();
+            if stream.latency_probe.is_some() {
+                return Err("A latency measurement is already running".to_string());
+            }
+            stream.latency_probe = Some(sender);
+        }
+
+        let state = self.state.lock().unwrap();
+        let original = match self.camera_io(state, |camera| camera.extension_control(unit, selector)).1 {
+            None => Err("Can't measure the latency, the camera is not open".to_string()),
+            Some(Err(e)) => Err(format!(
+                "The illuminator control {}:{} is not available: {} ({:?})",
+                unit, selector, e, e
+            )),
+            Some(Ok(original)) if original.is_empty() => {
+                Err(format!("The illuminator control {}:{} has no value", unit, selector))
+            }
+            Some(Ok(original)) => Ok(original),
+        };
+        let result = original.and_then(|original| {
+            let result = self.run_latency_probe(&samples, (unit, selector), &original);
+            let state = self.state.lock().unwrap();
+            let restore = |camera: &mut Camera| camera.set_extension_control(unit, selector, &original);
+            if let Some(Err(e)) = self.camera_io(state, restore).1 {
+                gst::warning!(CAT, imp = self, "Could not restore the illuminator: {} ({:?})", e, e);
+            }
+            result
+        });
+        self.stream.lock().unwrap().latency_probe = None;
+        result
+    }
+
+    fn run_latency_probe(
+        &self,
+        samples: &mpsc::Receiver<(gst::ClockTime, f64)>,
+        (unit, selector): (u8, u8),
+        original: &[u8],
+    ) -> Result<gst::Structure, String> {
+        let no_frames = || "No frames while measuring the latency".to_string();
+
+        let baseline = (0..LATENCY_BASELINE_FRAMES)
+            .map(|_| samples.recv_timeout(LATENCY_TIMEOUT).map(|(_, luma)| luma))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| no_frames())?;
+        let mean = baseline.iter().sum::<f64>() / baseline.len() as f64;
+        let deviation =
+            (baseline.iter().map(|luma| (luma - mean).powi(2)).sum::<f64>() / baseline.len() as f64).sqrt();
+        let threshold = (3.0 * deviation).max(2.0);
+
+        // Switch it off, or on if it was off
+        let mut toggled = vec![0; original.len()];
+        if original.iter().all(|&b| b == 0) {
+            toggled[0] = 1;
+        }
+        let camera_error = |e: uvc::Error| {
+            format!("Could not toggle the illuminator control {}:{}: {} ({:?})", unit, selector, e, e)
+        };
+        let state = self.state.lock().unwrap();
+        let toggle = |camera: &mut Camera| {
+            // Same monotonic clock as the frame capture times
+            let toggled_at = gst::get_timestamp();
+            camera.set_extension_control(unit, selector, &toggled).map(|()| toggled_at)
+        };
+        let toggled_at = self.camera_io(state, toggle).1.ok_or_else(no_frames)?.map_err(camera_error)?;
+
+        let deadline = std::time::Instant::now() + LATENCY_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            let Ok((capture_time, luma)) = samples.recv_timeout(remaining) else {
+                return Err(format!("No change in brightness within {:?} of toggling the illuminator", LATENCY_TIMEOUT));
+            };
+            let change = (luma - mean).abs();
+            if capture_time <= toggled_at || change <= threshold {
+                continue;
+            }
+
+            let latency = capture_time - toggled_at;
+            let frame_duration = self
+                .stream
+                .lock()
+                .unwrap()
+                .frame_duration()
+                .ok_or_else(|| "The caps have no fixed frame rate".to_string())?;
+            let frames = latency.nseconds().div_ceil(frame_duration.nseconds());
+            // How far the change stands out of the noise of the baseline
+            let confidence = ((change - threshold) / change).clamp(0.0, 1.0);
+            return Ok(gst::Structure::builder("bigeyesrc-latency")
+                .field("latency", latency.nseconds())
+                .field("frames", frames as u32)
+                .field("confidence", confidence)
+                .build());
+        }
+    }
+
+    // Hands the brightness of a frame to a running latency measurement
+    fn feed_latency_probe(&self, stream: &Stream, frame: &CapturedFrame) {
+        let Some(probe) = stream.latency_probe.as_ref() else {
+            return;
+        };
+        let (left, right) = process::mean_luma_halves_yuy2(
+            &frame.data,
+            frame.width as usize,
+            frame.height as usize,
+        );
+        let _ = probe.send((frame.meta.capture_time, (left + right) / 2.0));
+    }
+
+    // Describes the camera, through the open handle while streaming or by
+    // opening it just for that otherwise
+    fn probe_device(&self) -> Result<gst::Structure, String> {
+        let state = self.wait_for_device(self.state.lock().unwrap());
+        if let Some(camera) = state.camera.as_ref() {
+            let probe = camera.probe().ok_or("The open camera can't be described")?;
+            return Ok(probe_structure(&probe, true));
+        }
+
+        let serial = state.serial.clone();
+        let probe = self.unlocked_device_io(state, || capture::probe(serial.as_deref())).1;
+        Ok(probe_structure(&probe.map_err(|e| e.to_string())?, false))
+    }
+
+    // Opens the camera on its own queue, captures for SELF_TEST_DURATION and
+    // checks the frames. Returns the verdict, or why the test couldn't run.
+    fn self_test(&self) -> Result<gst::Structure, String> {
+        let settings = self.effective_settings();
+        let state = self.wait_for_device(self.state.lock().unwrap());
+        if self.stream.lock().unwrap().started_settings.is_some() {
+            return Err("Can't run the self-test while the element is streaming, set it to READY or NULL first".to_string());
+        }
+        let serial = self.config.lock().unwrap().serial.clone().or_else(|| state.serial.clone());
+        // start() waits for the test to finish rather than finding the
+        // camera busy
+        self.unlocked_device_io(state, || self.run_self_test(settings, serial)).1
+    }
+
+    fn run_self_test(&self, mut settings: Settings, serial: Option<String>) -> Result<gst::Structure, String> {
+        if settings.format == Format::Auto {
+            settings.format = self.pick_format(serial.as_deref());
+        }
+
+        let selector = capture::Selector {
+            serial,
+            interface: settings.interface,
+            format: match settings.format {
+                Format::Mjpeg | Format::Auto => capture::PixelFormat::Mjpeg,
+                Format::Raw => capture::PixelFormat::Yuy2,
+            },
+            width: WIDTH as u32,
+            height: HEIGHT as u32,
+            fps: FRAMES_SECOND as u32,
+            queue_size: MAX_QUEUED_FRAMES,
+            affinity_mask: settings.affinity_mask,
+            #[cfg(feature = "mock")]
+            test_pattern: settings.test_pattern,
+        };
+        let camera = Camera::open(&selector).map_err(|e| format!("Could not open the camera: {}", e))?;
+        let (size, expected_fps) = camera
+            .mode()
+            .map_or(((selector.width, selector.height), selector.fps), |mode| ((mode.width, mode.height), mode.fps));
+        gst::info!(CAT, imp = self, "Running the self-test at {}x{}@{}", size.0, size.1, expected_fps);
+
+        // Frames are checked as they arrive rather than kept, raw ones are
+        // big
+        let started = std::time::Instant::now();
+        let (mut frames, mut corrupt, mut skipped) = (0u32, 0u32, 0u64);
+        let mut captured: Option<(gst::ClockTime, gst::ClockTime)> = None;
+        let mut previous_sequence = None;
+        while let Some(remaining) = SELF_TEST_DURATION.checked_sub(started.elapsed()) {
+            let Some(frame) = camera.try_next(remaining) else {
+                continue;
+            };
+            let frame = CapturedFrame::from(frame);
+            frames += 1;
+            if (frame.width, frame.height) != size
+                || frame.data.len() < settings.min_frame_size as usize
+                || !self.validate_frame(&frame, &settings)
+            {
+                corrupt += 1;
+            }
+            // Sequence numbers the camera skipped are frames lost on the way
+            if let Some(previous) = previous_sequence {
+                skipped += u64::from(frame.meta.sequence.wrapping_sub(previous).saturating_sub(1));
+            }
+            previous_sequence = Some(frame.meta.sequence);
+            let first = captured.map_or(frame.meta.capture_time, |(first, _)| first);
+            captured = Some((first, frame.meta.capture_time));
+        }
+        let dropped = skipped + camera.dropped();
+        drop(camera);
+
+        let fps = match captured {
+            Some((first, last)) if last > first => f64::from(frames - 1) / (last - first).seconds_f64(),
+            _ => 0.0,
+        };
+        let expected = f64::from(expected_fps);
+        let passed = frames > 0 && corrupt == 0 && (fps - expected).abs() <= expected * SELF_TEST_FPS_TOLERANCE;
+        Ok(gst::Structure::builder("bigeyesrc-self-test")
+            .field("passed", passed)
+            .field("format", if settings.format == Format::Raw { "raw" } else { "mjpeg" })
+            .field("width", size.0)
+            .field("height", size.1)
+            .field("duration-ms", SELF_TEST_DURATION.as_millis() as u32)
+            .field("frames", frames)
+            .field("fps", fps)
+            .field("expected-fps", expected_fps)
+            .field("dropped", dropped)
+            .field("corrupt", corrupt)
+            .build())
+    }
+
+    // Pins the thread create() runs on, libuvc's thread pins itself with the
+    // same mask when the first frame arrives
+    fn pin_streaming_thread(&self, mask: u64) {
+        match capture::pin_thread(mask) {
+            Ok(()) => gst::info!(CAT, imp = self, "Pinned the streaming thread to CPUs {:#x}", mask),
+            Err(e) => gst::element_imp_warning!(
+                self,
+                gst::ResourceError::Settings,
+                ("Could not pin the capture threads to affinity-mask {:#x}", mask),
+                ["sched_setaffinity failed: {}", e]
+            ),
+        }
+    }
+
+    // Remembers that waiting for a frame took too long, for last-timeout
+    fn record_timeout(&self) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        self.stream.lock().unwrap().last_timeout = Some(gst::ClockTime::from_nseconds(now.as_nanos() as u64));
+        self.obj().notify("last-timeout");
+    }
+
+    // Moves `pts` just past the PTS of the previous buffer if it doesn't come
+    // after it. Capture times within a batch, repeats of the last frame and
+    // the clock can disagree, downstream must only see increasing ones.
+    fn monotonic_pts(&self, stream: &mut Stream, pts: Option<gst::ClockTime>) -> Option<gst::ClockTime> {
+        let pts = pts?;
+        let pts = match stream.last_pts {
+            Some(last) if pts <= last => {
+                let corrected = last + gst::ClockTime::from_nseconds(1);
+                stream.stats.pts_corrections += 1;
+                if !std::mem::replace(&mut stream.pts_warned, true) {
+                    gst::warning!(
+                        CAT,
+                        imp = self,
+                        "PTS {} does not come after the previous {}, pushing {} instead. Further corrections are only counted as pts-corrections",
+                        pts,
+                        last,
+                        corrected
+                    );
+                } else {
+                    gst::debug!(CAT, imp = self, "Corrected PTS {} to {}", pts, corrected);
+                }
+                corrected
+            }
+            _ => pts,
+        };
+        stream.last_pts = Some(pts);
+        Some(pts)
+    }
+
+    // Current running time of the element, if it has a clock
+    fn running_time(&self) -> Option<gst::ClockTime> {
+        let obj = self.obj();
+        let clock = obj.clock()?;
+        let base_time = obj.base_time()?;
+        clock.time().checked_sub(base_time)
+    }
+
+    // Cheap sanity check of a frame's payload
+    fn validate_frame(&self, frame: &CapturedFrame, settings: &Settings) -> bool {
+        let valid = match settings.format {
+            // A complete JPEG starts with SOI and ends with EOI, some
+            // cameras pad a few bytes after the EOI marker
+            Format::Mjpeg | Format::Auto => {
+                let tail = &frame.data[frame.data.len().saturating_sub(16)..];
+                frame.data.starts_with(&[0xff, 0xd8]) && tail.windows(2).any(|w| w == [0xff, 0xd9])
+            }
+            Format::Raw => frame.data.len() == (frame.width * frame.height * 2) as usize,
+        };
+
+        if !valid {
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Frame {} of {} bytes failed validation",
+                frame.meta.sequence,
+                frame.data.len()
+            );
+        }
+
+        valid
+    }
+
+    // Has create() push the frames queued right now, oldest first. With
+    // `eos` it ends the stream once they are out. Returns false if there is
+    // nothing to push.
+    fn start_drain(&self, stream: &mut Stream, eos: bool) -> bool {
+        stream.drain_eos |= eos;
+        if stream.drain_remaining.is_some() {
+            return true;
+        }
+        let queued = self.queue().len();
+        if stream.started_settings.is_none() || queued == 0 {
+            stream.drain_eos = false;
+            return false;
+        }
+        gst::debug!(CAT, imp = self, "Draining {} queued frame(s)", queued);
+        stream.drain_remaining = Some(queued);
+        self.draining.store(true, Ordering::SeqCst);
+        true
+    }
+
+    // Drains the queue before the element leaves PLAYING. The frames are
+    // pushed by the streaming thread, this only waits for it to take them.
+    // It gives up when the element is flushing, after DRAIN_TIMEOUT, or when
+    // create() took no frame within DRAIN_STALL.
+    fn drain_queue(&self) {
+        let mut stream = self.stream.lock().unwrap();
+        if !self.start_drain(&mut stream, false) {
+            return;
+        }
+
+        let deadline = std::time::Instant::now() + DRAIN_TIMEOUT;
+        while let Some(remaining) = stream.drain_remaining.filter(|&remaining| remaining > 0) {
+            let wait = deadline.saturating_duration_since(std::time::Instant::now()).min(DRAIN_STALL);
+            let result;
+            (stream, result) = self
+                .drained
+                .wait_timeout_while(stream, wait, |stream| {
+                    stream.drain_remaining == Some(remaining) && !self.flushing.load(Ordering::SeqCst)
+                })
+                .unwrap();
+            if self.flushing.load(Ordering::SeqCst) {
+                break;
+            }
+            if result.timed_out() {
+                gst::warning!(CAT, imp = self, "Gave up draining, {} frame(s) were not pushed", remaining);
+                break;
+            }
+        }
+        // A drain started by an EOS still has to end the stream
+        if !stream.drain_eos {
+            stream.drain_remaining = None;
+            self.draining.store(false, Ordering::SeqCst);
+        }
+    }
+
+    // The next frame to push while drain-on-stop runs, oldest first. None
+    // when not draining, and Eos after the last one of a drain started by
+    // an EOS.
+    fn next_drained_frame(&self, stream: &mut Stream) -> Result<Option<Vec<capture::Frame>>, gst::FlowError> {
+        if !self.draining.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+        match stream.drain_remaining {
+            None => Ok(None),
+            // Being called again means the last frame was pushed
+            Some(0) => {
+                stream.drain_remaining = None;
+                self.draining.store(false, Ordering::SeqCst);
+                if std::mem::take(&mut stream.drain_eos) {
+                    gst::debug!(CAT, imp = self, "Drained the queued frames, ending the stream");
+                    return Err(gst::FlowError::Eos);
+                }
+                Ok(None)
+            }
+            Some(remaining) => {
+                let frame = self.queue().try_next(std::time::Duration::ZERO);
+                stream.drain_remaining = Some(if frame.is_some() { remaining - 1 } else { 0 });
+                self.drained.notify_all();
+                Ok(Some(frame.into_iter().collect()))
+            }
+        }
+    }
+
+    // Keeps only the newest of the drained frames unless batching, and drops
+    // runts, corrupt ones and with dedup repeats. Returns the frames to push
+    // and the number of frames dropped as runts or corrupt.
+    fn accept_frames(
+        &self,
+        stream: &mut Stream,
+        mut queued: Vec<capture::Frame>,
+        batch: bool,
+        log_throttle: std::time::Duration,
+        settings: &Settings,
+    ) -> (Vec<CapturedFrame>, usize) {
+        if !batch && queued.len() > 1 {
+            // Only the newest frame is delivered, older ones are dropped
+            let dropped = queued.len() as u64 - 1;
+            stream.stats.dropped += dropped;
+            queued.drain(..queued.len() - 1);
+            if let Some(count) = stream.drop_log.hit(dropped, log_throttle) {
+                gst::warning!(CAT, imp = self, "Dropped {} frame(s) waiting to be pushed, downstream is too slow", count);
+            }
+        }
+        let mut frames = queued.into_iter().map(CapturedFrame::from).collect::<Vec<_>>();
+
+        // Zero-length and tiny frames come from transfers cut short on a
+        // marginal link, they'd only trip up decoders
+        let received = frames.len();
+        frames.retain(|frame| frame.data.len() >= settings.min_frame_size as usize);
+        let runts = received - frames.len();
+        if runts > 0 {
+            stream.stats.runt_frames += runts as u64;
+            if !std::mem::replace(&mut stream.runt_warned, true) {
+                gst::warning!(
+                    CAT,
+                    imp = self,
+                    "Dropped a frame smaller than min-frame-size={} bytes, further ones are only counted as runt-frames",
+                    settings.min_frame_size
+                );
+            } else {
+                gst::debug!(CAT, imp = self, "Dropped {} runt frame(s)", runts);
+            }
+        }
+
+        let received = frames.len();
+        frames.retain(|frame| self.validate_frame(frame, settings));
+        let corrupt = received - frames.len();
+        stream.stats.corrupt += corrupt as u64;
+        if corrupt > 0
+            && let Some(count) = stream.corrupt_log.hit(corrupt as u64, log_throttle)
+        {
+            gst::warning!(CAT, imp = self, "Dropped {} corrupt frame(s)", count);
+        }
+
+        // A frame delivered again isn't a hole in the stream, create() just
+        // waits for the next one
+        if settings.dedup {
+            let received = frames.len();
+            let mut last_sequence = stream.last_sequence;
+            frames.retain(|frame| last_sequence.replace(frame.meta.sequence) != Some(frame.meta.sequence));
+            let duplicates = received - frames.len();
+            if duplicates > 0 {
+                gst::debug!(CAT, imp = self, "Skipped {} frame(s) repeating the last sequence number", duplicates);
+                stream.stats.duplicates += duplicates as u64;
+            }
+        }
+        if let Some(frame) = frames.last() {
+            stream.last_sequence = Some(frame.meta.sequence);
+        }
+        (frames, runts + corrupt)
+    }
+
+    // Refines the clock-offset estimate with the capture time of a frame that
+    // is being timestamped right now
+    fn update_clock_offset(&self, stream: &mut Stream, capture_time: gst::ClockTime) {
+        let Some(clock) = self.obj().clock() else {
+            return;
+        };
+        let sample = clock.time().nseconds() as i64 - capture_time.nseconds() as i64;
+
+        let offset = match stream.clock_offset {
+            Some(offset) => offset + (sample - offset) / CLOCK_OFFSET_SMOOTHING,
+            None => sample,
+        };
+        stream.clock_offset = Some(offset);
+        gst::trace!(CAT, imp = self, "Clock offset sample {} ns, estimate {} ns", sample, offset);
+    }
+
+    // Compares a frame against the previous one and detects when more than
+    // freeze-threshold identical frames arrived in a row. Returns the number
+    // of frames and the sequence number the freeze is to be reported with.
+    fn check_freeze(&self, stream: &mut Stream, frame: &CapturedFrame, settings: &Settings) -> Option<(u32, u32)> {
+        if !settings.freeze_detection {
+            return None;
+        }
+
+        let hash = process::sparse_hash(&frame.data);
+        let freeze = &mut stream.freeze;
+
+        if freeze.last_hash != Some(hash) {
+            *freeze = FreezeState {
+                last_hash: Some(hash),
+                ..Default::default()
+            };
+            return None;
+        }
+
+        freeze.identical += 1;
+        let report = !freeze.reported && freeze.identical > settings.freeze_threshold;
+        if report {
+            freeze.reported = true;
+        }
+        let identical = freeze.identical;
+
+        stream.stats.frozen += 1;
+        if !report {
+            return None;
+        }
+        stream.stats.freezes += 1;
+        Some((identical + 1, frame.meta.sequence))
+    }
+
+    // Posts the warning about a freeze found by check_freeze(). Returns
+    // whether the device should be reset.
+    fn report_freeze(&self, (frames, sequence): (u32, u32), settings: &Settings) -> bool {
+        gst::element_imp_warning!(
+            self,
+            gst::StreamError::Failed,
+            ("The camera keeps sending the same frame"),
+            ["{} identical frames in a row up to sequence {}", frames, sequence]
+        );
+
+        settings.freeze_action == FreezeAction::Reset
+    }
+
+    // Measures the brightness of both eyes on every brightness-interval-th
+    // raw frame and posts a warning when an eye stays dark for too long
+    fn monitor_brightness(&self, stream: &mut Stream, frame: &CapturedFrame, settings: &Settings) -> Option<(f64, f64)> {
+        if !settings.enable_brightness_monitor
+            || settings.format != Format::Raw
+            || !frame.meta.sequence.is_multiple_of(settings.brightness_interval)
+        {
+            return None;
+        }
+
+        let (left, right) = process::mean_luma_halves_yuy2(
+            &frame.data,
+            frame.width as usize,
+            frame.height as usize,
+        );
+        gst::trace!(CAT, imp = self, "Brightness left {:.1} right {:.1}", left, right);
+
+        stream.stats.brightness = Some((left, right));
+
+        let duration = std::time::Duration::from_millis(settings.brightness_duration as u64);
+        for (eye, (name, luma)) in [("left", left), ("right", right)].into_iter().enumerate() {
+            if luma >= settings.brightness_threshold {
+                stream.dark_since[eye] = None;
+                stream.dark_warned[eye] = false;
+                continue;
+            }
+
+            let since = *stream.dark_since[eye].get_or_insert_with(std::time::Instant::now);
+            if !stream.dark_warned[eye] && since.elapsed() >= duration {
+                stream.dark_warned[eye] = true;
+                gst::element_imp_warning!(
+                    self,
+                    gst::StreamError::Failed,
+                    ("The {} eye image is dark", name),
+                    [
+                        "Mean brightness {:.1} has been below {:.1} for {} ms, check the illuminator and that the sensor is not covered",
+                        luma,
+                        settings.brightness_threshold,
+                        since.elapsed().as_millis()
+                    ]
+                );
+            }
+        }
+
+        Some((left, right))
+    }
+
+    // Applies the stall-action for a missing frame. Returns the buffer to push
+    // in its place, if any.
+    fn handle_stall(&self, settings: &Settings, duration: gst::ClockTime) -> Option<gst::Buffer> {
+        match settings.stall_action {
+            StallAction::Drop => None,
+            StallAction::Gap => {
+                let now = self.stream.lock().unwrap().timestamp(settings.pts_base, self.running_time());
+                if let Some(now) = now {
+                    gst::debug!(CAT, imp = self, "Pushing GAP at {}", now);
+                    let gap = gst::event::Gap::builder(now).duration(duration).build();
+                    if self.push_serialized_event(gap) {
+                        self.stream.lock().unwrap().stats.gaps += 1;
+                    }
+                }
+                None
+            }
+            StallAction::RepeatLast => {
+                let mut stream = self.stream.lock().unwrap();
+                let last = stream.last_buffer.as_ref()?;
+
+                // Shallow copy, the memory stays shared with the original
+                let mut buffer = last.copy();
+                {
+                    let buffer_ref = buffer.get_mut().unwrap();
+                    let pts = stream.timestamp(settings.pts_base, self.running_time());
+                    buffer_ref.set_pts(self.monotonic_pts(&mut stream, pts));
+                    buffer_ref.set_duration(duration);
+                    if let Some(mut meta) = FrameMeta::from_buffer(buffer_ref) {
+                        meta.repeat = true;
+                        meta.add(buffer_ref);
+                    }
+                }
+
+                stream.stats.repeated += 1;
+                stream.stats.delivered += 1;
+
+                gst::debug!(CAT, imp = self, "Repeating last frame as {:?}", buffer);
+                Some(buffer)
+            }
+        }
+    }
+
+    // Wraps a captured frame into a GStreamer buffer. Raw frames are scaled
+    // when their size differs from the negotiated `output` (width, height,
+    // stride).
+    fn frame_to_buffer(
+        &self,
+        mut frame: CapturedFrame,
+        pts: Option<gst::ClockTime>,
+        output: Option<RawOutput>,
+        duration: gst::ClockTime,
+        settings: &Settings,
+    ) -> gst::Buffer {
+        // Where the picture sits in a frame padded by output-aspect
+        let mut crop = None;
+        if settings.format == Format::Raw {
+            if settings.invert {
+                process::invert_yuy2(&mut frame.data);
+            }
+            if settings.normalize {
+                process::normalize_yuy2(&mut frame.data);
+            }
+            // The rest treats the anaglyph like a frame of that size
+            if settings.anaglyph(settings.format) {
+                frame.data = process::anaglyph_yuy2(&frame.data, (frame.width as usize, frame.height as usize));
+                (frame.width, frame.height) = anaglyph_size((frame.width, frame.height));
+            }
+            if let Some(RawOutput { width, height, stride, .. }) = output {
+                let size = (width as usize, height as usize);
+                // GRAY8 is taken from a tightly packed YUY2 frame of the output size
+                let yuy2_stride = if settings.mono { size.0 * 2 } else { stride };
+                // output-aspect scales the picture into the bars first
+                let picture = match settings.output_aspect(settings.format) {
+                    Some(_) => fitted_size((frame.width, frame.height), (width, height)),
+                    None => (width, height),
+                };
+                if picture != (width, height) {
+                    let picture_size = (picture.0 as usize, picture.1 as usize);
+                    if picture != (frame.width, frame.height) {
+                        frame.data = process::scale_yuy2(
+                            &frame.data,
+                            (frame.width as usize, frame.height as usize),
+                            picture_size,
+                            picture_size.0 * 2,
+                        );
+                    }
+                    frame.data =
+                        process::letterbox_yuy2(&frame.data, picture_size, size, yuy2_stride, settings.output_fill as u8);
+                    let (left, top) = process::letterbox_origin(picture_size, size);
+                    crop = Some((left as u32, top as u32, picture.0, picture.1));
+                } else if (width, height) != (frame.width, frame.height) {
+                    frame.data = process::scale_yuy2(
+                        &frame.data,
+                        (frame.width as usize, frame.height as usize),
+                        size,
+                        yuy2_stride,
+                    );
+                }
+                if settings.mono {
+                    frame.data = process::luma_yuy2(&frame.data, size, stride);
+                }
+            }
+        }
+
+        // Wrapped slices are read-only memory, so tee and other consumers
+        // share the buffer and anything that wants to write copies it first
+        if settings.checksum {
+            frame.meta.checksum = Some(meta::checksum(&frame.data));
+        }
+        let mut buffer = gst::Buffer::from_slice(frame.data);
+        {
+            let buffer_ref = buffer.get_mut().unwrap();
+
+            // Attach sequence number and capture time
+            frame.meta.add(buffer_ref);
+
+            if let Some(output) = output {
+                if output.video_meta {
+                    let _ = gst_video::VideoMeta::add_full(
+                        buffer_ref,
+                        gst_video::VideoFrameFlags::empty(),
+                        output.format,
+                        output.width,
+                        output.height,
+                        &[0],
+                        &[output.stride as i32],
+                    );
+                }
+                if output.crop_meta
+                    && let Some(rect) = crop
+                {
+                    gst_video::VideoCropMeta::add(buffer_ref, rect);
+                }
+            }
+
+            buffer_ref.set_pts(pts);
+
+            // Set duration based on the negotiated framerate
+            buffer_ref.set_duration(duration);
+        }
+
+        buffer
+    }
+}
+
+// This trait registers our type with the GObject object system and
+// provides the entry points for creating a new instance and setting
+// up the class data
+#[glib::object_subclass]
+impl ObjectSubclass for BigEyeSrc {
+    const NAME: &'static str = "BigEyeSrc";
+    type Type = super::BigEyeSrc;
+    type ParentType = gst_base::PushSrc;
+}
+
+// Implementation of glib::Object virtual methods
+impl ObjectImpl for BigEyeSrc {
+    // Metadata for the properties
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: LazyLock<Vec<glib::ParamSpec>> = LazyLock::new(|| {
+            vec![
+                glib::ParamSpecUInt::builder("interface")
+                    .nick("Interface")
+                    .blurb("Index of the UVC video streaming interface to open")
+                    .default_value(DEFAULT_INTERFACE)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("serialize-meta")
+                    .nick("Serialize Meta")
+                    .blurb("Push a serialized event carrying the BigEyeFrameMeta before each buffer, so it survives gdppay/shmsink")
+                    .default_value(DEFAULT_SERIALIZE_META)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("batch")
+                    .nick("Batch")
+                    .blurb("Push all frames queued since the last buffer as one buffer list instead of only the newest frame")
+                    .default_value(DEFAULT_BATCH)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecEnum::builder_with_default("format", DEFAULT_FORMAT)
+                    .nick("Format")
+                    .blurb("Format to request from the camera")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("invert")
+                    .nick("Invert")
+                    .blurb("Invert pixel intensities (raw format only)")
+                    .default_value(DEFAULT_INVERT)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("normalize")
+                    .nick("Normalize")
+                    .blurb("Stretch pixel intensities of each frame to the full range (raw format only)")
+                    .default_value(DEFAULT_NORMALIZE)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecEnum::builder_with_default("stall-action", DEFAULT_STALL_ACTION)
+                    .nick("Stall Action")
+                    .blurb("What to push when a frame is corrupt or none arrived within gap-threshold")
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("gap-threshold")
+                    .nick("Gap Threshold")
+                    .blurb("Time in milliseconds without a frame after which the stall-action is applied")
+                    .minimum(1)
+                    .default_value(DEFAULT_GAP_THRESHOLD)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("enable-brightness-monitor")
+                    .nick("Enable Brightness Monitor")
+                    .blurb("Measure the mean brightness of each eye and warn when one stays dark (raw format only)")
+                    .default_value(DEFAULT_ENABLE_BRIGHTNESS_MONITOR)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("brightness-interval")
+                    .nick("Brightness Interval")
+                    .blurb("Measure the brightness on every Nth frame")
+                    .minimum(1)
+                    .default_value(DEFAULT_BRIGHTNESS_INTERVAL)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecDouble::builder("brightness-threshold")
+                    .nick("Brightness Threshold")
+                    .blurb("Mean luma (0-255) below which an eye is considered dark")
+                    .minimum(0.0)
+                    .maximum(255.0)
+                    .default_value(DEFAULT_BRIGHTNESS_THRESHOLD)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("brightness-duration")
+                    .nick("Brightness Duration")
+                    .blurb("Time in milliseconds an eye has to stay dark before a warning is posted")
+                    .default_value(DEFAULT_BRIGHTNESS_DURATION)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("freeze-detection")
+                    .nick("Freeze Detection")
+                    .blurb("Detect the camera repeatedly sending the exact same frame")
+                    .default_value(DEFAULT_FREEZE_DETECTION)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("freeze-threshold")
+                    .nick("Freeze Threshold")
+                    .blurb("Number of consecutive identical frames after which the camera is considered frozen")
+                    .minimum(1)
+                    .default_value(DEFAULT_FREEZE_THRESHOLD)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecEnum::builder_with_default("freeze-action", DEFAULT_FREEZE_ACTION)
+                    .nick("Freeze Action")
+                    .blurb("What to do when the camera is frozen")
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("disable-autosuspend")
+                    .nick("Disable Autosuspend")
+                    .blurb("Keep the USB port of the camera from autosuspending while streaming (Linux only)")
+                    .default_value(DEFAULT_DISABLE_AUTOSUSPEND)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("strict-resolution")
+                    .nick("Strict Resolution")
+                    .blurb("Only negotiate frame sizes the device supports. Otherwise raw output of any size is produced by scaling from the nearest supported one")
+                    .default_value(DEFAULT_STRICT_RESOLUTION)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("multiview")
+                    .nick("Multiview")
+                    .blurb("Advertise the frames as a side-by-side stereo pair, with multiview caps for raw and a bigeye-multiview-mode tag for MJPEG output")
+                    .default_value(DEFAULT_MULTIVIEW)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("mono")
+                    .nick("Mono")
+                    .blurb("Output raw frames as GRAY8 with only the luma of the camera's YUY2, the IR sensor carries no color")
+                    .default_value(DEFAULT_MONO)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("anaglyph")
+                    .nick("Anaglyph")
+                    .blurb("Debug preview: combine both eyes of raw frames into one red/cyan anaglyph of half the width, left eye red")
+                    .default_value(DEFAULT_ANAGLYPH)
+                    .mutable_ready()
+                    .build(),
+                gst::ParamSpecFraction::builder("output-aspect")
+                    .nick("Output Aspect")
+                    .blurb("Pad raw frames with bars to this aspect ratio, keeping the picture's own, 0/1 for no padding")
+                    .minimum(gst::Fraction::from_integer(0))
+                    .maximum(gst::Fraction::from_integer(MAX_SCALED_SIZE))
+                    .default_value(DEFAULT_OUTPUT_ASPECT)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("output-fill")
+                    .nick("Output Fill")
+                    .blurb("Luma of the bars added by output-aspect, with neutral chroma")
+                    .maximum(u8::MAX.into())
+                    .default_value(DEFAULT_OUTPUT_FILL)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("startup-delay")
+                    .nick("Startup Delay")
+                    .blurb("Time in milliseconds to wait before looking for the camera when starting, for USB hubs that enumerate it late after power-up")
+                    .default_value(DEFAULT_STARTUP_DELAY)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("open-retries")
+                    .nick("Open Retries")
+                    .blurb("How many more times to try opening the camera when starting fails, for hubs with transient USB errors")
+                    .default_value(DEFAULT_OPEN_RETRIES)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("open-retry-delay")
+                    .nick("Open Retry Delay")
+                    .blurb("Time in milliseconds before the first retry of open-retries, doubled for every further one")
+                    .minimum(1)
+                    .default_value(DEFAULT_OPEN_RETRY_DELAY)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt::builder("illuminator-unit")
+                    .nick("Illuminator Unit")
+                    .blurb("ID of the UVC extension unit with the IR illuminator control, 0 if unknown")
+                    .maximum(255)
+                    .default_value(DEFAULT_ILLUMINATOR_UNIT)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("illuminator-selector")
+                    .nick("Illuminator Selector")
+                    .blurb("Selector of the IR illuminator control within illuminator-unit")
+                    .maximum(255)
+                    .default_value(DEFAULT_ILLUMINATOR_SELECTOR)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("illuminator-mode-selector")
+                    .nick("Illuminator Mode Selector")
+                    .blurb("Selector of the illuminator mode control within illuminator-unit, 0 if unknown")
+                    .maximum(255)
+                    .default_value(DEFAULT_ILLUMINATOR_MODE_SELECTOR)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecEnum::builder_with_default("illuminator-mode", DEFAULT_ILLUMINATOR_MODE)
+                    .nick("Illuminator Mode")
+                    .blurb("Flood or structured light, written to illuminator-mode-selector of illuminator-unit on firmware that supports it")
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("exposure")
+                    .nick("Exposure")
+                    .blurb("Manual exposure time of both eyes in units of 100 microseconds, 0 leaves the exposure to the camera")
+                    .default_value(DEFAULT_EXPOSURE)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("left-exposure")
+                    .nick("Left Exposure")
+                    .blurb("Exposure time of the left eye on cameras with an exposure control per sensor, 0 to use exposure")
+                    .default_value(DEFAULT_EXPOSURE)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("right-exposure")
+                    .nick("Right Exposure")
+                    .blurb("Exposure time of the right eye on cameras with an exposure control per sensor, 0 to use exposure")
+                    .default_value(DEFAULT_EXPOSURE)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecInt::builder("gain")
+                    .nick("Gain")
+                    .blurb("Gain of both eyes, -1 leaves the gain to the camera")
+                    .minimum(-1)
+                    .maximum(u16::MAX as i32)
+                    .default_value(DEFAULT_GAIN)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecInt::builder("left-gain")
+                    .nick("Left Gain")
+                    .blurb("Gain of the left eye on cameras with a gain control per sensor, -1 to use gain")
+                    .minimum(-1)
+                    .maximum(u16::MAX as i32)
+                    .default_value(DEFAULT_GAIN)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecInt::builder("right-gain")
+                    .nick("Right Gain")
+                    .blurb("Gain of the right eye on cameras with a gain control per sensor, -1 to use gain")
+                    .minimum(-1)
+                    .maximum(u16::MAX as i32)
+                    .default_value(DEFAULT_GAIN)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("log-throttle")
+                    .nick("Log Throttle")
+                    .blurb("Minimum time in milliseconds between two warnings about corrupt frames, dropped frames or stalls, 0 to log every one")
+                    .default_value(DEFAULT_LOG_THROTTLE)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecUInt::builder("min-frame-size")
+                    .nick("Min Frame Size")
+                    .blurb("Frames from the camera smaller than this many bytes are dropped as runts, 0 to keep all")
+                    .default_value(DEFAULT_MIN_FRAME_SIZE)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("dedup")
+                    .nick("Dedup")
+                    .blurb("Skip frames with the same sequence number as the last one pushed, so every buffer is a distinct exposure")
+                    .default_value(DEFAULT_DEDUP)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("drain-on-stop")
+                    .nick("Drain On Stop")
+                    .blurb("When the element leaves PLAYING or an EOS event is sent to it, push the frames captured so far instead of dropping them")
+                    .default_value(DEFAULT_DRAIN_ON_STOP)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("checksum")
+                    .nick("Checksum")
+                    .blurb("Add a hash of the frame bytes to the frame meta, for checking downstream that they arrived intact")
+                    .default_value(DEFAULT_CHECKSUM)
+                    .mutable_playing()
+                    .build(),
+                glib::ParamSpecBoolean::builder("low-latency")
+                    .nick("Low Latency")
+                    .blurb("Keep only the newest frame, overwriting it as the next one arrives, and push it as soon as it does. Overrides batch.")
+                    .default_value(DEFAULT_LOW_LATENCY)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecUInt64::builder("affinity-mask")
+                    .nick("Affinity Mask")
+                    .blurb("CPUs the streaming thread and the libuvc thread delivering frames are pinned to, one bit per CPU, 0 for no pinning (Linux only)")
+                    .default_value(DEFAULT_AFFINITY_MASK)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("ae-priority")
+                    .nick("AE Priority")
+                    .blurb("Let auto-exposure lower the frame rate in dim scenes, false keeps the negotiated frame rate")
+                    .default_value(DEFAULT_AE_PRIORITY)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecBoolean::builder("auto-degrade")
+                    .nick("Auto Degrade")
+                    .blurb("When the USB link can't carry the requested mode, stream at the next lower mode the camera lists instead of failing")
+                    .default_value(DEFAULT_AUTO_DEGRADE)
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecEnum::builder_with_default("pts-base", DEFAULT_PTS_BASE)
+                    .nick("PTS Base")
+                    .blurb("Whether buffer timestamps are the running time or start at 0 with the first buffer")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecString::builder("config-file")
+                    .nick("Config File")
+                    .blurb("File with property values and camera settings applied when going to READY, properties set on the element take precedence")
+                    .mutable_ready()
+                    .build(),
+                glib::ParamSpecInt64::builder("clock-offset")
+                    .nick("Clock Offset")
+                    .blurb("Smoothed offset in nanoseconds of the pipeline clock relative to the frame capture timestamps")
+                    .read_only()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("active-format")
+                    .nick("Active Format")
+                    .blurb("Width, height, fps and fourcc the camera agreed to, only set while streaming")
+                    .read_only()
+                    .build(),
+                glib::ParamSpecBoolean::builder("capturing")
+                    .nick("Capturing")
+                    .blurb("Whether the camera is streaming, see the stop-capture and start-capture signals")
+                    .default_value(true)
+                    .read_only()
+                    .build(),
+                glib::ParamSpecUInt64::builder("last-timeout")
+                    .nick("Last Timeout")
+                    .blurb("Wall-clock time in nanoseconds since the Unix epoch at which waiting for a frame last exceeded gap-threshold, GST_CLOCK_TIME_NONE if it never did")
+                    .default_value(u64::MAX)
+                    .read_only()
+                    .build(),
+                glib::ParamSpecUInt::builder("queue-level")
+                    .nick("Queue Level")
+                    .blurb("Number of frames currently waiting in the queue to be pushed")
+                    .maximum(MAX_QUEUED_FRAMES as u32)
+                    .read_only()
+                    .build(),
+                glib::ParamSpecBoxed::builder::<gst::Structure>("stats")
+                    .nick("Statistics")
+                    .blurb("Streaming statistics")
+                    .read_only()
+                    .build(),
+                #[cfg(feature = "mock")]
+                glib::ParamSpecEnum::builder_with_default("test-pattern", DEFAULT_TEST_PATTERN)
+                    .nick("Test Pattern")
+                    .blurb("Synthetic image generated by the mock backend")
+                    .mutable_ready()
+                    .build(),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    // Action signals
+    fn signals() -> &'static [glib::subclass::Signal] {
+        static SIGNALS: LazyLock<Vec<glib::subclass::Signal>> = LazyLock::new(|| {
+            vec![
+                // Resets the USB port of the camera and reopens it. Returns
+                // whether the camera is streaming again.
+                glib::subclass::Signal::builder("reset-device")
+                    .action()
+                    .return_type::<bool>()
+                    .class_handler(|args| {
+                        let element = args[0].get::<super::BigEyeSrc>().expect("signal arg");
+                        let imp = element.imp();
+                        let result = imp.reset_usb_port();
+                        match result {
+                            Ok(()) => gst::element_imp_info!(
+                                imp,
+                                gst::ResourceError::Settings,
+                                ("The camera was reset and is streaming again")
+                            ),
+                            Err(ref err) => {
+                                gst::error!(CAT, imp = imp, "Device reset failed: {:?}", err);
+                                imp.post_failure(err.clone());
+                            }
+                        }
+                        Some(result.is_ok().to_value())
+                    })
+                    .build(),
+                // Measure the time from switching the illuminator to the
+                // first frame showing it. Returns the result, or NULL if it
+                // couldn't be measured.
+                glib::subclass::Signal::builder("measure-latency")
+                    .action()
+                    .return_type::<Option<gst::Structure>>()
+                    .class_handler(|args| {
+                        let element = args[0].get::<super::BigEyeSrc>().expect("signal arg");
+                        let imp = element.imp();
+                        match imp.measure_latency() {
+                            Ok(result) => {
+                                gst::info!(CAT, imp = imp, "Measured latency: {}", result);
+                                let _ = element.post_message(
+                                    gst::message::Element::builder(result.clone()).src(&element).build(),
+                                );
+                                Some(Some(result).to_value())
+                            }
+                            Err(err) => {
+                                gst::element_imp_warning!(
+                                    imp,
+                                    gst::ResourceError::Settings,
+                                    ("Could not measure the latency"),
+                                    ["{}", err]
+                                );
+                                Some(None::<gst::Structure>.to_value())
+                            }
+                        }
+                    })
+                    .build(),
+                // Describe the camera without starting a stream: its
+                // identity, modes and controls. Returns NULL if there is
+                // no camera.
+                glib::subclass::Signal::builder("probe-device")
+                    .action()
+                    .return_type::<Option<gst::Structure>>()
+                    .class_handler(|args| {
+                        let element = args[0].get::<super::BigEyeSrc>().expect("signal arg");
+                        let imp = element.imp();
+                        match imp.probe_device() {
+                            Ok(probe) => {
+                                gst::debug!(CAT, imp = imp, "Probed device: {}", probe);
+                                Some(Some(probe).to_value())
+                            }
+                            Err(err) => {
+                                gst::element_imp_warning!(
+                                    imp,
+                                    gst::ResourceError::NotFound,
+                                    ("Could not probe the camera"),
+                                    ["{}", err]
+                                );
+                                Some(None::<gst::Structure>.to_value())
+                            }
+                        }
+                    })
+                    .build(),
+                // Close the camera while staying in PLAYING, and open it
+                // again. Both return whether they succeeded.
+                glib::subclass::Signal::builder("stop-capture")
+                    .action()
+                    .return_type::<bool>()
+                    .class_handler(|args| {
+                        let element = args[0].get::<super::BigEyeSrc>().expect("signal arg");
+                        let imp = element.imp();
+                        let result = imp.stop_capture();
+                        if let Err(ref err) = result {
+                            gst::warning!(CAT, imp = imp, "stop-capture failed: {:?}", err);
+                        }
+                        Some(result.is_ok().to_value())
+                    })
+                    .build(),
+                glib::subclass::Signal::builder("start-capture")
+                    .action()
+                    .return_type::<bool>()
+                    .class_handler(|args| {
+                        let element = args[0].get::<super::BigEyeSrc>().expect("signal arg");
+                        let imp = element.imp();
+                        let result = imp.start_capture();
+                        if let Err(ref err) = result {
+                            gst::error!(CAT, imp = imp, "start-capture failed: {:?}", err);
+                            imp.post_failure(err.clone());
+                        }
+                        Some(result.is_ok().to_value())
+                    })
+                    .build(),
+                // Stop and restart the stream without closing the camera.
+                // Returns whether a stream was restarted.
+                glib::subclass::Signal::builder("restart-stream")
+                    .action()
+                    .return_type::<bool>()
+                    .class_handler(|args| {
+                        let element = args[0].get::<super::BigEyeSrc>().expect("signal arg");
+                        let imp = element.imp();
+                        let restarted = imp.restart_stream().unwrap_or_else(|err| {
+                            gst::error!(CAT, imp = imp, "restart-stream failed: {:?}", err);
+                            imp.post_failure(err);
+                            false
+                        });
+                        Some(restarted.to_value())
+                    })
+                    .build(),
+                // Capture for a second from a camera that isn't streaming
+                // and check the frames. Returns the verdict, or NULL if the
+                // test couldn't run.
+                glib::subclass::Signal::builder("self-test")
+                    .action()
+                    .return_type::<Option<gst::Structure>>()
+                    .class_handler(|args| {
+                        let element = args[0].get::<super::BigEyeSrc>().expect("signal arg");
+                        let imp = element.imp();
+                        match imp.self_test() {
+                            Ok(verdict) => {
+                                gst::info!(CAT, imp = imp, "Self-test result: {}", verdict);
+                                Some(Some(verdict).to_value())
+                            }
+                            Err(err) => {
+                                gst::element_imp_warning!(
+                                    imp,
+                                    gst::ResourceError::Failed,
+                                    ("Could not run the self-test"),
+                                    ["{}", err]
+                                );
+                                Some(None::<gst::Structure>.to_value())
+                            }
+                        }
+                    })
+                    .build(),
+            ]
+        });
+
+        SIGNALS.as_ref()
+    }
+
+    // Called whenever a value of a property is changed. It can be called
+    // at any time from any thread.
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        if applies_at_start(pspec.name())
+            && self.stream.lock().unwrap().started_settings.is_some()
+        {
+            gst::warning!(
+                CAT,
+                imp = self,
+                "{} changed while streaming, it takes effect on the next start",
+                pspec.name()
+            );
+        }
+
+        {
+            let mut config = self.config.lock().unwrap();
+            if !config.applying {
+                config.explicit.insert(pspec.name().to_string());
+            }
+        }
+
+        match pspec.name() {
+            "interface" => {
+                let mut settings = self.settings.lock().unwrap();
+                let interface = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing interface from {} to {}",
+                    settings.interface,
+                    interface
+                );
+                settings.interface = interface;
+            }
+            "serialize-meta" => {
+                let mut settings = self.settings.lock().unwrap();
+                let serialize_meta = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing serialize-meta from {} to {}",
+                    settings.serialize_meta,
+                    serialize_meta
+                );
+                settings.serialize_meta = serialize_meta;
+            }
+            "batch" => {
+                let mut settings = self.settings.lock().unwrap();
+                let batch = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing batch from {} to {}",
+                    settings.batch,
+                    batch
+                );
+                settings.batch = batch;
+            }
+            "format" => {
+                let mut settings = self.settings.lock().unwrap();
+                let format = value.get::<Format>().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing format from {:?} to {:?}",
+                    settings.format,
+                    format
+                );
+                settings.format = format;
+            }
+            "invert" => {
+                let mut settings = self.settings.lock().unwrap();
+                let invert = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing invert from {} to {}",
+                    settings.invert,
+                    invert
+                );
+                settings.invert = invert;
+            }
+            "normalize" => {
+                let mut settings = self.settings.lock().unwrap();
+                let normalize = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing normalize from {} to {}",
+                    settings.normalize,
+                    normalize
+                );
+                settings.normalize = normalize;
+            }
+            "stall-action" => {
+                let mut settings = self.settings.lock().unwrap();
+                let stall_action = value.get::<StallAction>().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing stall-action from {:?} to {:?}",
+                    settings.stall_action,
+                    stall_action
+                );
+                settings.stall_action = stall_action;
+            }
+            "gap-threshold" => {
+                let mut settings = self.settings.lock().unwrap();
+                let gap_threshold = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing gap-threshold from {} to {}",
+                    settings.gap_threshold,
+                    gap_threshold
+                );
+                settings.gap_threshold = gap_threshold;
+            }
+            "enable-brightness-monitor" => {
+                let mut settings = self.settings.lock().unwrap();
+                let enable_brightness_monitor = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing enable-brightness-monitor from {} to {}",
+                    settings.enable_brightness_monitor,
+                    enable_brightness_monitor
+                );
+                settings.enable_brightness_monitor = enable_brightness_monitor;
+            }
+            "brightness-interval" => {
+                let mut settings = self.settings.lock().unwrap();
+                let brightness_interval = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing brightness-interval from {} to {}",
+                    settings.brightness_interval,
+                    brightness_interval
+                );
+                settings.brightness_interval = brightness_interval;
+            }
+            "brightness-threshold" => {
+                let mut settings = self.settings.lock().unwrap();
+                let brightness_threshold = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing brightness-threshold from {} to {}",
+                    settings.brightness_threshold,
+                    brightness_threshold
+                );
+                settings.brightness_threshold = brightness_threshold;
+            }
+            "brightness-duration" => {
+                let mut settings = self.settings.lock().unwrap();
+                let brightness_duration = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing brightness-duration from {} to {}",
+                    settings.brightness_duration,
+                    brightness_duration
+                );
+                settings.brightness_duration = brightness_duration;
+            }
+            "freeze-detection" => {
+                let mut settings = self.settings.lock().unwrap();
+                let freeze_detection = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing freeze-detection from {} to {}",
+                    settings.freeze_detection,
+                    freeze_detection
+                );
+                settings.freeze_detection = freeze_detection;
+            }
+            "freeze-threshold" => {
+                let mut settings = self.settings.lock().unwrap();
+                let freeze_threshold = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing freeze-threshold from {} to {}",
+                    settings.freeze_threshold,
+                    freeze_threshold
+                );
+                settings.freeze_threshold = freeze_threshold;
+            }
+            "freeze-action" => {
+                let mut settings = self.settings.lock().unwrap();
+                let freeze_action = value.get::<FreezeAction>().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing freeze-action from {:?} to {:?}",
+                    settings.freeze_action,
+                    freeze_action
+                );
+                settings.freeze_action = freeze_action;
+            }
+            "disable-autosuspend" => {
+                let mut settings = self.settings.lock().unwrap();
+                let disable_autosuspend = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing disable-autosuspend from {} to {}",
+                    settings.disable_autosuspend,
+                    disable_autosuspend
+                );
+                settings.disable_autosuspend = disable_autosuspend;
+            }
+            "strict-resolution" => {
+                let mut settings = self.settings.lock().unwrap();
+                let strict_resolution = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing strict-resolution from {} to {}",
+                    settings.strict_resolution,
+                    strict_resolution
+                );
+                settings.strict_resolution = strict_resolution;
+            }
+            "multiview" => {
+                let mut settings = self.settings.lock().unwrap();
+                let multiview = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing multiview from {} to {}",
+                    settings.multiview,
+                    multiview
+                );
+                settings.multiview = multiview;
+            }
+            "mono" => {
+                let mut settings = self.settings.lock().unwrap();
+                let mono = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing mono from {} to {}",
+                    settings.mono,
+                    mono
+                );
+                settings.mono = mono;
+            }
+            "anaglyph" => {
+                let mut settings = self.settings.lock().unwrap();
+                let anaglyph = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing anaglyph from {} to {}",
+                    settings.anaglyph,
+                    anaglyph
+                );
+                settings.anaglyph = anaglyph;
+            }
+            "output-aspect" => {
+                let mut settings = self.settings.lock().unwrap();
+                let output_aspect = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing output-aspect from {} to {}",
+                    settings.output_aspect,
+                    output_aspect
+                );
+                settings.output_aspect = output_aspect;
+            }
+            "output-fill" => {
+                let mut settings = self.settings.lock().unwrap();
+                let output_fill = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing output-fill from {} to {}",
+                    settings.output_fill,
+                    output_fill
+                );
+                settings.output_fill = output_fill;
+            }
+            "startup-delay" => {
+                let mut settings = self.settings.lock().unwrap();
+                let startup_delay = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing startup-delay from {} to {}",
+                    settings.startup_delay,
+                    startup_delay
+                );
+                settings.startup_delay = startup_delay;
+            }
+            "open-retries" => {
+                let mut settings = self.settings.lock().unwrap();
+                let open_retries = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing open-retries from {} to {}",
+                    settings.open_retries,
+                    open_retries
+                );
+                settings.open_retries = open_retries;
+            }
+            "open-retry-delay" => {
+                let mut settings = self.settings.lock().unwrap();
+                let open_retry_delay = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing open-retry-delay from {} to {}",
+                    settings.open_retry_delay,
+                    open_retry_delay
+                );
+                settings.open_retry_delay = open_retry_delay;
+            }
+            "illuminator-unit" => {
+                let mut settings = self.settings.lock().unwrap();
+                let illuminator_unit = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing illuminator-unit from {} to {}",
+                    settings.illuminator_unit,
+                    illuminator_unit
+                );
+                settings.illuminator_unit = illuminator_unit;
+            }
+            "illuminator-selector" => {
+                let mut settings = self.settings.lock().unwrap();
+                let illuminator_selector = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing illuminator-selector from {} to {}",
+                    settings.illuminator_selector,
+                    illuminator_selector
+                );
+                settings.illuminator_selector = illuminator_selector;
+            }
+            "illuminator-mode-selector" => {
+                let mut settings = self.settings.lock().unwrap();
+                let illuminator_mode_selector = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing illuminator-mode-selector from {} to {}",
+                    settings.illuminator_mode_selector,
+                    illuminator_mode_selector
+                );
+                settings.illuminator_mode_selector = illuminator_mode_selector;
+            }
+            "illuminator-mode" => {
+                let mut settings = self.settings.lock().unwrap();
+                let illuminator_mode = value.get::<IlluminatorMode>().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing illuminator-mode from {:?} to {:?}",
+                    settings.illuminator_mode,
+                    illuminator_mode
+                );
+                settings.illuminator_mode = illuminator_mode;
+                drop(settings);
+                self.update_illuminator_mode();
+            }
+            "exposure" => {
+                let mut settings = self.settings.lock().unwrap();
+                let exposure = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing exposure from {} to {}",
+                    settings.exposure,
+                    exposure
+                );
+                settings.exposure = exposure;
+                drop(settings);
+                self.update_eye_controls();
+            }
+            "left-exposure" => {
+                let mut settings = self.settings.lock().unwrap();
+                let left_exposure = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing left-exposure from {} to {}",
+                    settings.left_exposure,
+                    left_exposure
+                );
+                settings.left_exposure = left_exposure;
+                drop(settings);
+                self.update_eye_controls();
+            }
+            "right-exposure" => {
+                let mut settings = self.settings.lock().unwrap();
+                let right_exposure = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing right-exposure from {} to {}",
+                    settings.right_exposure,
+                    right_exposure
+                );
+                settings.right_exposure = right_exposure;
+                drop(settings);
+                self.update_eye_controls();
+            }
+            "gain" => {
+                let mut settings = self.settings.lock().unwrap();
+                let gain = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing gain from {} to {}",
+                    settings.gain,
+                    gain
+                );
+                settings.gain = gain;
+                drop(settings);
+                self.update_eye_controls();
+            }
+            "left-gain" => {
+                let mut settings = self.settings.lock().unwrap();
+                let left_gain = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing left-gain from {} to {}",
+                    settings.left_gain,
+                    left_gain
+                );
+                settings.left_gain = left_gain;
+                drop(settings);
+                self.update_eye_controls();
+            }
+            "right-gain" => {
+                let mut settings = self.settings.lock().unwrap();
+                let right_gain = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing right-gain from {} to {}",
+                    settings.right_gain,
+                    right_gain
+                );
+                settings.right_gain = right_gain;
+                drop(settings);
+                self.update_eye_controls();
+            }
+            "log-throttle" => {
+                let mut settings = self.settings.lock().unwrap();
+                let log_throttle = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing log-throttle from {} to {}",
+                    settings.log_throttle,
+                    log_throttle
+                );
+                settings.log_throttle = log_throttle;
+            }
+            "min-frame-size" => {
+                let mut settings = self.settings.lock().unwrap();
+                let min_frame_size = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing min-frame-size from {} to {}",
+                    settings.min_frame_size,
+                    min_frame_size
+                );
+                settings.min_frame_size = min_frame_size;
+            }
+            "dedup" => {
+                let mut settings = self.settings.lock().unwrap();
+                let dedup = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing dedup from {} to {}",
+                    settings.dedup,
+                    dedup
+                );
+                settings.dedup = dedup;
+            }
+            "drain-on-stop" => {
+                let mut settings = self.settings.lock().unwrap();
+                let drain_on_stop = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing drain-on-stop from {} to {}",
+                    settings.drain_on_stop,
+                    drain_on_stop
+                );
+                settings.drain_on_stop = drain_on_stop;
+            }
+            "checksum" => {
+                let mut settings = self.settings.lock().unwrap();
+                let checksum = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing checksum from {} to {}",
+                    settings.checksum,
+                    checksum
+                );
+                settings.checksum = checksum;
+            }
+            "low-latency" => {
+                let mut settings = self.settings.lock().unwrap();
+                let low_latency = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing low-latency from {} to {}",
+                    settings.low_latency,
+                    low_latency
+                );
+                settings.low_latency = low_latency;
+            }
+            "affinity-mask" => {
+                let mut settings = self.settings.lock().unwrap();
+                let affinity_mask = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing affinity-mask from {:#x} to {:#x}",
+                    settings.affinity_mask,
+                    affinity_mask
+                );
+                settings.affinity_mask = affinity_mask;
+            }
+            "ae-priority" => {
+                let mut settings = self.settings.lock().unwrap();
+                let ae_priority = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing ae-priority from {} to {}",
+                    settings.ae_priority,
+                    ae_priority
+                );
+                settings.ae_priority = ae_priority;
+            }
+            "auto-degrade" => {
+                let mut settings = self.settings.lock().unwrap();
+                let auto_degrade = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing auto-degrade from {} to {}",
+                    settings.auto_degrade,
+                    auto_degrade
+                );
+                settings.auto_degrade = auto_degrade;
+            }
+            "pts-base" => {
+                let mut settings = self.settings.lock().unwrap();
+                let pts_base = value.get::<PtsBase>().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing pts-base from {:?} to {:?}",
+                    settings.pts_base,
+                    pts_base
+                );
+                settings.pts_base = pts_base;
+            }
+            "config-file" => {
+                let mut config = self.config.lock().unwrap();
+                let file = value.get().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing config-file from {:?} to {:?}",
+                    config.file,
+                    file
+                );
+                config.file = file;
+            }
+            #[cfg(feature = "mock")]
+            "test-pattern" => {
+                let mut settings = self.settings.lock().unwrap();
+                let test_pattern = value.get::<TestPattern>().expect("type checked upstream");
+                gst::info!(
+                    CAT,
+                    imp = self,
+                    "Changing test-pattern from {:?} to {:?}",
+                    settings.test_pattern,
+                    test_pattern
+                );
+                settings.test_pattern = test_pattern;
+            }
+            _ => unimplemented!(),
         }
     }
-}
-
-// Struct containing all the element data
-#[derive(Default)]
-pub struct BigEyeSrc {
-    state: Mutex<State>,
-}
 
-impl BigEyeSrc {}
-
-// This trait registers our type with the GObject object system and
-// provides the entry points for creating a new instance and setting
-// up the class data
-#[glib::object_subclass]
-impl ObjectSubclass for BigEyeSrc {
-    const NAME: &'static str = "BigEyeSrc";
-    type Type = super::BigEyeSrc;
-    type ParentType = gst_base::PushSrc;
-}
+    // Called whenever a value of a property is read. It can be called
+    // at any time from any thread.
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "interface" => {
+                let settings = self.settings.lock().unwrap();
+                settings.interface.to_value()
+            }
+            "serialize-meta" => {
+                let settings = self.settings.lock().unwrap();
+                settings.serialize_meta.to_value()
+            }
+            "batch" => {
+                let settings = self.settings.lock().unwrap();
+                settings.batch.to_value()
+            }
+            "format" => {
+                let settings = self.settings.lock().unwrap();
+                settings.format.to_value()
+            }
+            "invert" => {
+                let settings = self.settings.lock().unwrap();
+                settings.invert.to_value()
+            }
+            "normalize" => {
+                let settings = self.settings.lock().unwrap();
+                settings.normalize.to_value()
+            }
+            "stall-action" => {
+                let settings = self.settings.lock().unwrap();
+                settings.stall_action.to_value()
+            }
+            "gap-threshold" => {
+                let settings = self.settings.lock().unwrap();
+                settings.gap_threshold.to_value()
+            }
+            "enable-brightness-monitor" => {
+                let settings = self.settings.lock().unwrap();
+                settings.enable_brightness_monitor.to_value()
+            }
+            "brightness-interval" => {
+                let settings = self.settings.lock().unwrap();
+                settings.brightness_interval.to_value()
+            }
+            "brightness-threshold" => {
+                let settings = self.settings.lock().unwrap();
+                settings.brightness_threshold.to_value()
+            }
+            "brightness-duration" => {
+                let settings = self.settings.lock().unwrap();
+                settings.brightness_duration.to_value()
+            }
+            "freeze-detection" => {
+                let settings = self.settings.lock().unwrap();
+                settings.freeze_detection.to_value()
+            }
+            "freeze-threshold" => {
+                let settings = self.settings.lock().unwrap();
+                settings.freeze_threshold.to_value()
+            }
+            "freeze-action" => {
+                let settings = self.settings.lock().unwrap();
+                settings.freeze_action.to_value()
+            }
+            "disable-autosuspend" => {
+                let settings = self.settings.lock().unwrap();
+                settings.disable_autosuspend.to_value()
+            }
+            "strict-resolution" => {
+                let settings = self.settings.lock().unwrap();
+                settings.strict_resolution.to_value()
+            }
+            "multiview" => {
+                let settings = self.settings.lock().unwrap();
+                settings.multiview.to_value()
+            }
+            "mono" => {
+                let settings = self.settings.lock().unwrap();
+                settings.mono.to_value()
+            }
+            "anaglyph" => {
+                let settings = self.settings.lock().unwrap();
+                settings.anaglyph.to_value()
+            }
+            "output-aspect" => {
+                let settings = self.settings.lock().unwrap();
+                settings.output_aspect.to_value()
+            }
+            "output-fill" => {
+                let settings = self.settings.lock().unwrap();
+                settings.output_fill.to_value()
+            }
+            "startup-delay" => {
+                let settings = self.settings.lock().unwrap();
+                settings.startup_delay.to_value()
+            }
+            "open-retries" => {
+                let settings = self.settings.lock().unwrap();
+                settings.open_retries.to_value()
+            }
+            "open-retry-delay" => {
+                let settings = self.settings.lock().unwrap();
+                settings.open_retry_delay.to_value()
+            }
+            "illuminator-unit" => {
+                let settings = self.settings.lock().unwrap();
+                settings.illuminator_unit.to_value()
+            }
+            "illuminator-selector" => {
+                let settings = self.settings.lock().unwrap();
+                settings.illuminator_selector.to_value()
+            }
+            "illuminator-mode-selector" => {
+                let settings = self.settings.lock().unwrap();
+                settings.illuminator_mode_selector.to_value()
+            }
+            "illuminator-mode" => {
+                let settings = self.settings.lock().unwrap();
+                settings.illuminator_mode.to_value()
+            }
+            "exposure" => {
+                let settings = self.settings.lock().unwrap();
+                settings.exposure.to_value()
+            }
+            "left-exposure" => {
+                let settings = self.settings.lock().unwrap();
+                settings.left_exposure.to_value()
+            }
+            "right-exposure" => {
+                let settings = self.settings.lock().unwrap();
+                settings.right_exposure.to_value()
+            }
+            "gain" => {
+                let settings = self.settings.lock().unwrap();
+                settings.gain.to_value()
+            }
+            "left-gain" => {
+                let settings = self.settings.lock().unwrap();
+                settings.left_gain.to_value()
+            }
+            "right-gain" => {
+                let settings = self.settings.lock().unwrap();
+                settings.right_gain.to_value()
+            }
+            "log-throttle" => {
+                let settings = self.settings.lock().unwrap();
+                settings.log_throttle.to_value()
+            }
+            "min-frame-size" => {
+                let settings = self.settings.lock().unwrap();
+                settings.min_frame_size.to_value()
+            }
+            "dedup" => {
+                let settings = self.settings.lock().unwrap();
+                settings.dedup.to_value()
+            }
+            "drain-on-stop" => {
+                let settings = self.settings.lock().unwrap();
+                settings.drain_on_stop.to_value()
+            }
+            "checksum" => {
+                let settings = self.settings.lock().unwrap();
+                settings.checksum.to_value()
+            }
+            "low-latency" => {
+                let settings = self.settings.lock().unwrap();
+                settings.low_latency.to_value()
+            }
+            "affinity-mask" => {
+                let settings = self.settings.lock().unwrap();
+                settings.affinity_mask.to_value()
+            }
+            "ae-priority" => {
+                let settings = self.settings.lock().unwrap();
+                settings.ae_priority.to_value()
+            }
+            "auto-degrade" => {
+                let settings = self.settings.lock().unwrap();
+                settings.auto_degrade.to_value()
+            }
+            "pts-base" => {
+                let settings = self.settings.lock().unwrap();
+                settings.pts_base.to_value()
+            }
+            "config-file" => {
+                let config = self.config.lock().unwrap();
+                config.file.to_value()
+            }
+            #[cfg(feature = "mock")]
+            "test-pattern" => {
+                let settings = self.settings.lock().unwrap();
+                settings.test_pattern.to_value()
+            }
+            "clock-offset" => {
+                let stream = self.stream.lock().unwrap();
+                stream.clock_offset.unwrap_or(0).to_value()
+            }
+            "active-format" => {
+                let state = self.state.lock().unwrap();
+                let mode = state.camera.as_ref().and_then(Camera::mode);
+                mode.map(|mode| {
+                    gst::Structure::builder("application/x-bigeyesrc-format")
+                        .field("width", mode.width)
+                        .field("height", mode.height)
+                        .field("fps", mode.fps)
+                        .field("fourcc", String::from_utf8_lossy(&mode.fourcc).as_ref())
+                        .build()
+                })
+                .to_value()
+            }
+            "capturing" => {
+                let stream = self.stream.lock().unwrap();
+                stream.capturing.to_value()
+            }
+            "last-timeout" => {
+                let stream = self.stream.lock().unwrap();
+                stream.last_timeout.to_value()
+            }
+            "queue-level" => {
+                let queued = self.queue().len();
+                (queued as u32).to_value()
+            }
+            "stats" => {
+                let state = self.state.lock().unwrap();
+                let mut stats = self.stream.lock().unwrap().stats;
+                stats.dropped += self.queue().dropped();
+                #[cfg(target_os = "linux")]
+                {
+                    stats.autosuspend_disabled = self.autosuspend_restore.lock().unwrap().is_some();
+                }
+                stats.high_water_mark = self.queue().high_water_mark() as u64;
+                stats.usb_speed = state.camera.as_ref().and_then(Camera::usb_speed).map(usb_speed_name);
+                stats.to_structure().to_value()
+            }
+            _ => unimplemented!(),
+        }
+    }
 
-// Implementation of glib::Object virtual methods
-impl ObjectImpl for BigEyeSrc {
     // Called right after construction of a new instance
     fn constructed(&self) {
         // Call the parent class' ::constructed() implementation first
@@ -105,12 +4025,13 @@ impl ElementImpl for BigEyeSrc {
             // Define Capabilities (Caps)
             // sink: None, this is a source
             // source: "image/jpeg, width=(int)800, height=(int)400, framerate=(fraction)90/1"
-            let caps = gst::Caps::builder("image/jpeg")
-                .field("width", WIDTH)
-                .field("height", HEIGHT)
-                .field("framerate", gst::Fraction::new(FRAMES_SECOND, 1))
-                .build();
-            
+            //      or "video/x-raw, format=(string)YUY2, width=(int)800, height=(int)400, framerate=(fraction)90/1"
+            //      or the same with format=(string)GRAY8 for mono=true
+            let caps = [(Format::Mjpeg, false), (Format::Raw, false), (Format::Raw, true)]
+                .into_iter()
+                .map(|(format, mono)| format_caps(format, mono))
+                .collect::<gst::Caps>();
+
             // Make source pad template
             let src_pad_template = gst::PadTemplate::new(
                 "src",
@@ -138,107 +4059,232 @@ impl ElementImpl for BigEyeSrc {
             self.obj().set_live(true);
         }
 
+        // The config file is read first so start_camera() sees its values
+        if let gst::StateChange::NullToReady = transition
+            && let Err(err) = self.load_config_file()
+        {
+            self.post_failure(err);
+            return Err(gst::StateChangeError);
+        }
+        if let gst::StateChange::ReadyToPaused = transition
+            && let Err(err) = self.start_camera()
+        {
+            self.post_failure(err);
+            return Err(gst::StateChangeError);
+        }
+
+        // BaseSrc stops calling create() of a live source as soon as it is
+        // paused, so drain-on-stop has to push the frames while leaving
+        // PLAYING. By PAUSED→READY and stop() nothing is streaming anymore.
+        if transition == gst::StateChange::PlayingToPaused && self.settings.lock().unwrap().drain_on_stop {
+            self.drain_queue();
+        }
+
         // Call the parent class' implementation of ::change_state()
-        self.parent_change_state(transition)
+        let result = self.parent_change_state(transition);
+        if result.is_err() && transition == gst::StateChange::ReadyToPaused {
+            let _ = BaseSrcImpl::stop(self);
+        }
+        result
+    }
+
+    // An EOS sent to the element makes BaseSrc stop after the current
+    // create(). With drain-on-stop create() pushes the queued frames first
+    // and then ends the stream itself, so the sender doesn't wait for them.
+    fn send_event(&self, event: gst::Event) -> bool {
+        let drain_on_stop = self.settings.lock().unwrap().drain_on_stop;
+        if event.type_() == gst::EventType::Eos
+            && drain_on_stop
+            && self.obj().current_state() == gst::State::Playing
+            && self.start_drain(&mut self.stream.lock().unwrap(), true)
+        {
+            return true;
+        }
+        self.parent_send_event(event)
     }
 }
 
 // Implementation of gst_base::BaseSrc virtual methods
 impl BaseSrcImpl for BigEyeSrc {
+    // Only offer the caps of the configured format, at the sizes the device
+    // supports once it is open. Raw output can be scaled to any size unless
+    // strict-resolution is set. With format=auto both formats are offered,
+    // the one picked for the USB link first.
+    fn caps(&self, filter: Option<&gst::Caps>) -> Option<gst::Caps> {
+        let settings = self.effective_settings();
+        let state = self.state.lock().unwrap();
+        let formats = match settings.format {
+            Format::Auto => vec![Format::Mjpeg, Format::Raw],
+            Format::Mjpeg if state.auto_format => vec![Format::Mjpeg, Format::Raw],
+            Format::Raw if state.auto_format => vec![Format::Raw, Format::Mjpeg],
+            format => vec![format],
+        };
+        let mut caps = gst::Caps::new_empty();
+        for format in formats {
+            caps.merge(self.output_caps(format, &settings, &state));
+        }
+        drop(state);
+
+        match filter {
+            Some(filter) => Some(filter.intersect_with_mode(&caps, gst::CapsIntersectMode::First)),
+            None => Some(caps),
+        }
+    }
+
+    // The frames don't come from a pool, only the metas downstream lists
+    // are taken from the query: VideoMeta to describe the stride of raw
+    // frames, VideoCropMeta to mark the picture inside output-aspect bars.
+    // BigEyeFrameMeta is attached regardless.
+    fn decide_allocation(&self, query: &mut gst::query::Allocation) -> Result<(), gst::LoggableError> {
+        let video_meta = query.find_allocation_meta::<gst_video::VideoMeta>().is_some();
+        let crop_meta = query.find_allocation_meta::<gst_video::VideoCropMeta>().is_some();
+        gst::debug!(
+            CAT,
+            imp = self,
+            "Downstream supports VideoMeta: {}, VideoCropMeta: {}",
+            video_meta,
+            crop_meta
+        );
+
+        let mut stream = self.stream.lock().unwrap();
+        stream.video_meta = video_meta;
+        stream.crop_meta = crop_meta;
+        drop(stream);
+
+        self.parent_decide_allocation(query)
+    }
+
+    // Prefer the native size when downstream leaves it open
+    fn fixate(&self, mut caps: gst::Caps) -> gst::Caps {
+        caps.truncate();
+        {
+            let caps = caps.make_mut();
+            let s = caps.structure_mut(0).unwrap();
+            s.fixate_field_nearest_int("width", WIDTH);
+            s.fixate_field_nearest_int("height", HEIGHT);
+        }
+        self.parent_fixate(caps)
+    }
+
     // Called whenever the input/output caps are changing
+    // Picks the device mode for the negotiated size and restarts the stream
+    // if the device isn't streaming at it yet
     fn set_caps(&self, caps: &gst::Caps) -> Result<(), gst::LoggableError> {
+        // BaseSrc fixates the caps it negotiates, but gst_base_src_set_caps()
+        // passes on whatever it is given. A device mode can't be picked
+        // from a range, and the caps event for them would be invalid anyway.
+        if let Some(reason) = unfixed_reason(caps) {
+            return Err(gst::loggable_error!(CAT, "Caps {} are not fixed: {}", caps, reason));
+        }
         let info = gst_video::VideoInfo::from_caps(caps).map_err(|_| {
             gst::loggable_error!(CAT, "Failed to build `VideoInfo` from caps {}", caps)
         })?;
 
         gst::debug!(CAT, imp = self, "Configuring for caps {}", caps);
 
+        let mut settings = self.effective_settings();
         let mut state = self.state.lock().unwrap();
-        state.info = Some(info);
-        drop(state);
-
-        Ok(())
-    }
-
-    // Called when starting, so we can initialize the stream
-    // This initializes the UVC context, then gets the device, opens it, creates the stream, and then starts it
-    // Box::leak is a standard function in Rust. It consumes the Box and leaks it onto the heap, so it lives for the duration of the program.
-    // Read more at https://doc.rust-lang.org/std/boxed/struct.Box.html
-    fn start(&self) -> Result<(), gst::ErrorMessage> {
-        gst::info!(CAT, imp = self, "Starting video capture");
-
-        let mut state = self.state.lock().unwrap();
-        
-        // Initialize context
-        let ctx = Box::leak(Box::new(uvc::Context::new().map_err(|e| {
-            gst::error_msg!(
-                gst::ResourceError::OpenRead,
-                ["Could not create context: {:?}", e]
-            )
-        })?));
-        gst::info!(CAT, imp = self, "Context created");
-
-        // Get a BSB2E device using Vendor ID and Product ID
-        let dev = Box::leak(Box::new(ctx.find_device(Some(0x35bd), Some(0x0202), None).map_err(|e| {
-            gst::error_msg!(
-                gst::ResourceError::NotFound,
-                ["Could not find device: {:?}", e]
-            )
-        })?));
-        gst::info!(CAT, imp = self, "Device found");
 
-        // Open the device
-        let devh = Box::leak(Box::new(dev.open().map_err(|e| {
-            gst::error_msg!(
-                gst::ResourceError::OpenRead,
-                ["Could not open device: {:?}", e]
-            )
-        })?));
-        gst::info!(CAT, imp = self, "Device opened");
+        // Downstream may still pick the format format=auto didn't choose
+        let caps_format = match caps.structure(0) {
+            Some(s) if s.name() == "image/jpeg" => Format::Mjpeg,
+            _ => Format::Raw,
+        };
+        let switch_format = state.auto_format && caps_format != settings.format;
+        if switch_format {
+            gst::info!(
+                CAT,
+                imp = self,
+                "Downstream chose {:?} over the {:?} picked by format=auto",
+                caps_format,
+                settings.format
+            );
+            settings.format = caps_format;
+            if let Some(started) = self.stream.lock().unwrap().started_settings.as_mut() {
+                started.format = caps_format;
+            }
+        }
 
-        // Configure for MJPEG format at 800x400@90fps
-        let format = uvc::StreamFormat {
-            width: (WIDTH as u32),
-            height: (HEIGHT as u32),
-            fps: (FRAMES_SECOND as u32),
-            format: uvc::FrameFormat::MJPEG,
+        let wanted = (info.width(), info.height());
+        // With output-aspect the picture is captured at the size without
+        // the bars, and an anaglyph at twice its width. The device mode
+        // the output was made from wins if there is one.
+        let anaglyph = settings.anaglyph(settings.format);
+        let made_from = state
+            .sizes
+            .iter()
+            .copied()
+            .find(|&size| settings.output_size(settings.format, size) == wanted);
+        let wanted = match made_from {
+            Some(size) => size,
+            None => {
+                let native = (WIDTH as u32, HEIGHT as u32);
+                let picture = match settings.output_aspect(settings.format) {
+                    Some(_) => fitted_size(if anaglyph { anaglyph_size(native) } else { native }, wanted),
+                    None => wanted,
+                };
+                if anaglyph { (picture.0 * 2, picture.1) } else { picture }
+            }
+        };
+        let capture_size = if state.sizes.contains(&wanted) {
+            wanted
+        } else if settings.strict_resolution {
+            return Err(gst::loggable_error!(
+                CAT,
+                "{}x{} is not supported by the device (supported: {:?})",
+                wanted.0,
+                wanted.1,
+                state.sizes
+            ));
+        } else {
+            // Without descriptors keep streaming at the current size and scale
+            let nearest = nearest_size(&state.sizes, wanted).unwrap_or(state.capture_size);
+            gst::warning!(
+                CAT,
+                imp = self,
+                "Device has no {}x{} mode, capturing at {}x{} and scaling in software",
+                wanted.0,
+                wanted.1,
+                nearest.0,
+                nearest.1
+            );
+            nearest
         };
+        // The camera takes whole frame rates
+        let fps = info.fps();
+        if fps.numer() <= 0 {
+            return Err(gst::loggable_error!(CAT, "Caps {} have no fixed frame rate", caps));
+        }
+        let capture_fps = (fps.numer() as f64 / fps.denom() as f64).round() as u32;
+        self.stream.lock().unwrap().info = Some(info);
 
-        // Get stream handle
-        let streamh = Box::leak(Box::new(devh.get_stream_handle_with_format(format).map_err(|e| {
-            gst::error_msg!(
-                gst::ResourceError::Settings,
-                ["Could not open stream with format: {:?}", e]
-            )
-        })?));
-        gst::info!(CAT, imp = self, "Stream handle obtained");
-
-        // Start the stream with a callback that stores frame data
-        let latest_frame = state.latest_frame.clone();   
-        let stream = streamh
-            .start_stream(
-                move |frame, context| {
-                    // Store the frame data as bytes
-                    let mut locked = context.lock().unwrap();
-                    *locked = Some(frame.to_bytes().to_vec());
-                },
-                latest_frame.clone(),
-            )
-            .map_err(|e| {
-                gst::error_msg!(
-                    gst::ResourceError::OpenRead,
-                    ["Could not start stream: {:?}", e]
-                )
-            })?;
+        let restart = state.camera.is_some()
+            && (switch_format || capture_size != state.capture_size || capture_fps != state.capture_fps);
+        state.capture_size = capture_size;
+        state.capture_fps = capture_fps;
+        drop(state);
 
-        gst::info!(CAT, imp = self, "Stream started successfully");
-        eprintln!("Stream started, waiting for frames...");
+        // The caps of MJPEG output don't make it through a decoder, so the
+        // stereo layout goes out as a tag. BaseSrc pushes it after the segment.
+        if settings.format == Format::Mjpeg && settings.multiview {
+            let mode = gst_video::VideoMultiviewMode::SideBySide.to_caps_string().unwrap();
+            let mut tags = gst::TagList::new();
+            tags.get_mut()
+                .unwrap()
+                .add::<MultiviewModeTag>(&mode.as_str(), gst::TagMergeMode::Replace);
+            self.obj().send_event(gst::event::Tag::new(tags));
+        }
 
-        state.stream = Some(stream);
+        if restart {
+            self.reset_device()
+                .map_err(|err| gst::loggable_error!(CAT, "Could not switch to the negotiated mode: {:?}", err))?;
+        }
 
-        drop(state);
+        Ok(())
+    }
 
-        gst::info!(CAT, imp = self, "Started video capture");
+    // The camera was already opened by change_state(), see start_camera()
+    fn start(&self) -> Result<(), gst::ErrorMessage> {
         Ok(())
     }
 
@@ -247,15 +4293,25 @@ impl BaseSrcImpl for BigEyeSrc {
     fn stop(&self) -> Result<(), gst::ErrorMessage> {
         gst::info!(CAT, imp = self, "Stopping video capture");
         
-        let mut state = self.state.lock().unwrap();
-        
-        // Stop the stream (will be dropped automatically)
-        if let Some(stream) = state.stream.take() {
-            stream.stop();
-        }
+        let mut state = self.wait_for_device(self.state.lock().unwrap());
         
-        // Clear the latest frame
-        *state.latest_frame.lock().unwrap() = None;
+        // Stop the stream and close the device (released on drop)
+        self.close_camera(&mut state);
+
+        state.auto_format = false;
+        state.sizes.clear();
+        state.modes.clear();
+        state.serial = None;
+        let mut stream = self.stream.lock().unwrap();
+        // Clear the retained buffer
+        stream.last_buffer = None;
+        stream.info = None;
+        stream.started_settings = None;
+        // An EOS still waiting for a drain goes away like a pending one
+        stream.drain_remaining = None;
+        stream.drain_eos = false;
+        self.draining.store(false, Ordering::SeqCst);
+        drop(stream);
         
         drop(state);
 
@@ -263,9 +4319,65 @@ impl BaseSrcImpl for BigEyeSrc {
         Ok(())
     }
 
+    // Makes create() return instead of waiting for frames
+    fn unlock(&self) -> Result<(), gst::ErrorMessage> {
+        self.flushing.store(true, Ordering::SeqCst);
+        // Taking each lock once makes sure a waiter either already saw the
+        // flag or is waiting and gets notified
+        drop(self.state.lock().unwrap());
+        self.wakeup.notify_all();
+        drop(self.stream.lock().unwrap());
+        self.drained.notify_all();
+        Ok(())
+    }
+
+    fn unlock_stop(&self) -> Result<(), gst::ErrorMessage> {
+        self.flushing.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
     fn is_seekable(&self) -> bool {
         false
     }
+
+    // An endless live stream has no size
+    fn size(&self) -> Option<u64> {
+        None
+    }
+
+    // BaseSrc also seeks without a seek event to set up the first segment,
+    // which has to succeed for the element to start. Anything that would
+    // actually move the stream is refused, like seek events in event().
+    fn do_seek(&self, segment: &mut gst::Segment) -> bool {
+        let at_start = segment
+            .downcast_ref::<gst::ClockTime>()
+            .is_none_or(|segment| segment.start().is_none_or(|start| start.is_zero()));
+        if segment.rate() != 1.0 || !at_start {
+            gst::debug!(CAT, imp = self, "Refusing to seek to {:?}, the camera is live", segment);
+            return false;
+        }
+        self.parent_do_seek(segment)
+    }
+
+    // A live camera can't seek, refuse seeks up front instead of letting
+    // BaseSrc attempt one
+    fn event(&self, event: &gst::Event) -> bool {
+        if let gst::EventView::Seek(_) = event.view() {
+            gst::debug!(CAT, imp = self, "Refusing seek, the camera is live and not seekable");
+            return false;
+        }
+        self.parent_event(event)
+    }
+
+    fn query(&self, query: &mut gst::QueryRef) -> bool {
+        if let gst::QueryViewMut::Seeking(q) = query.view_mut() {
+            let format = q.format();
+            let none = gst::GenericFormattedValue::none_for_format(format);
+            q.set(false, none, none);
+            return true;
+        }
+        BaseSrcImplExt::parent_query(self, query)
+    }
 }
 
 impl PushSrcImpl for BigEyeSrc {
@@ -274,62 +4386,277 @@ impl PushSrcImpl for BigEyeSrc {
         &self,
         _buffer: Option<&mut gst::BufferRef>,
     ) -> Result<CreateSuccess, gst::FlowError> {
-        // Get latest frame
-        let state = self.state.lock().unwrap();
-        let latest_frame = state.latest_frame.clone();
-        drop(state);  // Release the state lock early
+        // Frames are pulled from the queue, and the stream lock is only
+        // taken for short steps, never across a wait or the conversion of a
+        // frame. Property getters and setters and device I/O on other
+        // threads don't hold up the frames.
+        let settings = *self.settings.lock().unwrap();
+        let mut stream = self.stream.lock().unwrap();
+        let settings = stream.effective(settings);
+        if settings.affinity_mask != 0 && !std::mem::replace(&mut stream.thread_pinned, true) {
+            self.pin_streaming_thread(settings.affinity_mask);
+        }
+        let output = stream
+            .info
+            .as_ref()
+            .filter(|_| settings.format == Format::Raw)
+            .map(|info| RawOutput {
+                format: info.format(),
+                width: info.width(),
+                height: info.height(),
+                stride: info.stride()[0] as usize,
+                video_meta: stream.video_meta,
+                crop_meta: stream.crop_meta,
+            });
+
+        // Durations come from the caps, BaseSrc negotiates before the first
+        // create() so this only fails on a variable frame rate
+        let duration = stream.frame_duration();
+        drop(stream);
+        let Some(duration) = duration else {
+            gst::element_imp_error!(
+                self,
+                gst::CoreError::Negotiation,
+                ("No fixed frame rate was negotiated"),
+                ["create() called without caps or with a variable frame rate"],
+                details: error_details("negotiation", None)
+            );
+            return Err(gst::FlowError::NotNegotiated);
+        };
+
+        // Events can't be interleaved with a buffer list, so serialized meta forces single buffers
+        let batch = settings.batch && !settings.serialize_meta && !settings.low_latency;
+        let gap_threshold = std::time::Duration::from_millis(settings.gap_threshold as u64);
+        let log_throttle = std::time::Duration::from_millis(settings.log_throttle as u64);
 
-        // Get the latest frame from the camera
+        // Get the queued frames from the camera
         // Wait for a frame to be available with timeout
-        let frame_data = {
-            let start = std::time::Instant::now();
+        let mut frames = {
+            let mut start = std::time::Instant::now();
             let timeout = std::time::Duration::from_secs(5);
+            let mut stall_start = start;
             
             loop {
-                let mut latest = latest_frame.lock().unwrap();
-                match latest.take() {
-                    Some(data) => {
-                        gst::trace!(CAT, imp = self, "Got frame data of {} bytes", data.len());
-                        break data;
+                // Neither the stall handling nor the timeout apply while
+                // capture is stopped on purpose
+                if self.wait_for_capture(duration, settings.pts_base)? {
+                    start = std::time::Instant::now();
+                    stall_start = start;
+                }
+
+                let (frames, corrupt) = {
+                    let mut stream = self.stream.lock().unwrap();
+                    let queued = match self.next_drained_frame(&mut stream)? {
+                        Some(queued) => queued,
+                        None => self.queue().drain(),
+                    };
+                    if queued.is_empty() {
+                        (Vec::new(), 0)
+                    } else {
+                        self.accept_frames(&mut stream, queued, batch, log_throttle, &settings)
                     }
-                    None => {
-                        // No frame available yet, check timeout
-                        if start.elapsed() > timeout {
-                            drop(latest);
-                            gst::error!(CAT, imp = self, "No frame available, waiting...");
-                            return Err(gst::FlowError::Eos);
+                };
+
+                if !frames.is_empty() {
+                    gst::trace!(CAT, imp = self, "Got {} frame(s)", frames.len());
+                    break frames;
+                }
+
+                // A runt or corrupt frame or a stall all leave a hole in the stream
+                if corrupt > 0 || stall_start.elapsed() > gap_threshold {
+                    if corrupt == 0 {
+                        self.record_timeout();
+                        let throttled = self.stream.lock().unwrap().stall_log.hit(1, log_throttle);
+                        if let Some(count) = throttled {
+                            gst::warning!(
+                                CAT,
+                                imp = self,
+                                "No frame from the camera for {} ms, {} stall(s) since the last warning",
+                                stall_start.elapsed().as_millis(),
+                                count
+                            );
                         }
-                        // Wait a bit and retry
-                        drop(latest);
-                        std::thread::sleep(std::time::Duration::from_millis(5));
                     }
+                    if let Some(buffer) = self.handle_stall(&settings, duration) {
+                        return Ok(CreateSuccess::NewBuffer(buffer));
+                    }
+                    stall_start = std::time::Instant::now();
+                }
+
+                // No frame available yet, check timeout
+                if start.elapsed() > timeout {
+                    self.record_timeout();
+                    gst::error!(CAT, imp = self, "No frame available, waiting...");
+                    return Err(gst::FlowError::Eos);
+                }
+                // Wait a bit and retry. In low-latency mode the arrival of a
+                // frame ends the wait early.
+                if settings.low_latency {
+                    self.queue().wait(std::time::Duration::from_millis(5));
+                } else {
+                    std::thread::sleep(std::time::Duration::from_millis(5));
                 }
             }
         };
 
-        // Create a GStreamer buffer with the frame data
-        let mut buffer = gst::Buffer::from_slice(frame_data);
-        {
-            let buffer_ref = buffer.get_mut().unwrap();
-            
-            // For live sources, use the current running time for timestamping
-            let obj = self.obj();
-            if let Some(clock) = obj.clock() {
-                if let Some(base_time) = obj.base_time() {
-                    let now = clock.time();
-                    if let Some(pts) = now.checked_sub(base_time) {
-                        buffer_ref.set_pts(pts);
-                    }
+        // For live sources, use the current running time for timestamping
+        // the newest frame. Older frames of a batch are placed before it
+        // according to their capture times.
+        let dequeue_time = gst::get_timestamp();
+        let hook = self.frame_hook.read().unwrap().clone();
+        let newest_capture_time = frames.last().unwrap().meta.capture_time;
+        let mut stream = self.stream.lock().unwrap();
+        let mut freeze = None;
+        for frame in &frames {
+            freeze = self.check_freeze(&mut stream, frame, &settings).or(freeze);
+        }
+        let now = stream.timestamp(settings.pts_base, self.running_time());
+        self.update_clock_offset(&mut stream, newest_capture_time);
+        let mut timings = Vec::new();
+        let mut timestamps = Vec::with_capacity(frames.len());
+        for frame in &mut frames {
+            frame.meta.brightness = self.monitor_brightness(&mut stream, frame, &settings);
+            if settings.format == Format::Raw {
+                self.feed_latency_probe(&stream, frame);
+            }
+            let pts = now.map(|now| {
+                now.saturating_sub(newest_capture_time.saturating_sub(frame.meta.capture_time))
+            });
+            let pts = self.monotonic_pts(&mut stream, pts);
+            if hook.is_some() {
+                timings.push(super::FrameTiming {
+                    sequence: frame.meta.sequence,
+                    width: frame.width,
+                    height: frame.height,
+                    capture_time: frame.meta.capture_time,
+                    dequeue_time,
+                    pts,
+                });
+            }
+            timestamps.push(pts);
+        }
+        drop(stream);
+
+        // Processing and copying the frames is the slow part
+        let mut buffers = frames
+            .into_iter()
+            .zip(timestamps)
+            .map(|(frame, pts)| self.frame_to_buffer(frame, pts, output, duration, &settings))
+            .collect::<Vec<_>>();
+
+        let mut stream = self.stream.lock().unwrap();
+        stream.stats.delivered += buffers.len() as u64;
+        if std::mem::take(&mut stream.discont) {
+            buffers[0].make_mut().set_flags(gst::BufferFlags::DISCONT);
+        }
+        if settings.stall_action == StallAction::RepeatLast {
+            stream.last_buffer = buffers.last().cloned();
+        }
+        let capturing = stream.capturing;
+        let buffer_pushed = std::mem::replace(&mut stream.buffer_pushed, true);
+        drop(stream);
+
+        // The hook may call back into the element, so it runs unlocked
+        if let Some(hook) = &hook {
+            for timing in &timings {
+                hook(timing);
+            }
+        }
+        let reset = freeze.is_some_and(|freeze| self.report_freeze(freeze, &settings));
+        if reset && capturing {
+            self.reset_device().map_err(|err| {
+                self.post_failure(err);
+                gst::FlowError::Error
+            })?;
+        }
+
+        if buffers.len() == 1 {
+            let buffer = buffers.pop().unwrap();
+
+            // Buffer metas are lost over GDP, so optionally send them in-band
+            // as an event. The first buffer goes out before the segment.
+            if settings.serialize_meta {
+                if buffer_pushed {
+                    let meta = FrameMeta::from_buffer(&buffer).unwrap();
+                    self.obj().src_pad().push_event(meta.to_event());
+                } else {
+                    gst::debug!(CAT, imp = self, "Not pushing the meta event of the first buffer before the segment");
                 }
             }
-            
-            // Set duration based on framerate
-            let duration = gst::ClockTime::SECOND / (FRAMES_SECOND as u64);
-            buffer_ref.set_duration(duration);
+
+            gst::log!(CAT, imp = self, "Produced buffer {:?}", buffer);
+
+            Ok(CreateSuccess::NewBuffer(buffer))
+        } else {
+            let mut list = gst::BufferList::new_sized(buffers.len());
+            {
+                let list = list.get_mut().unwrap();
+                for buffer in buffers {
+                    list.add(buffer);
+                }
+            }
+
+            gst::log!(CAT, imp = self, "Produced buffer list of {} buffers", list.len());
+
+            Ok(CreateSuccess::NewBufferList(list))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_time_properties_are_flagged_mutable_ready() {
+        gst::init().unwrap();
+        let src = glib::Object::new::<super::super::BigEyeSrc>();
+        for &(name, _) in START_TIME_PROPERTIES {
+            assert!(src.find_property(name).is_some(), "no property {}", name);
+        }
+        for pspec in src.list_properties() {
+            if pspec.owner_type() != super::super::BigEyeSrc::static_type() {
+                continue;
+            }
+            let flagged = pspec.flags().contains(gst::PARAM_FLAG_MUTABLE_READY);
+            assert_eq!(flagged, applies_at_start(pspec.name()), "{}", pspec.name());
         }
+    }
+
+    #[test]
+    fn live_stream_has_no_size() {
+        gst::init().unwrap();
+        let src = glib::Object::new::<super::super::BigEyeSrc>();
+        assert_eq!(src.imp().size(), None);
+    }
+
+    #[test]
+    fn only_the_initial_segment_is_seekable() {
+        gst::init().unwrap();
+        let src = glib::Object::new::<super::super::BigEyeSrc>();
 
-        gst::log!(CAT, imp = self, "Produced buffer {:?}", buffer);
+        // BaseSrc sets up the first segment with a seek from the start
+        let mut segment = gst::FormattedSegment::<gst::ClockTime>::new().upcast();
+        assert!(src.imp().do_seek(&mut segment));
 
-        Ok(CreateSuccess::NewBuffer(buffer))
+        let mut segment = gst::FormattedSegment::<gst::ClockTime>::new();
+        segment.set_rate(2.0);
+        assert!(!src.imp().do_seek(&mut segment.upcast()));
+
+        let mut segment = gst::FormattedSegment::<gst::ClockTime>::new();
+        segment.set_start(gst::ClockTime::SECOND);
+        assert!(!src.imp().do_seek(&mut segment.upcast()));
+    }
+
+    #[test]
+    fn eye_values_per_unit() {
+        // Two units take the per-eye values, falling back to the shared one
+        assert_eq!(eye_values((Some(10), Some(20), None), &[1, 2]), (vec![(1, 20), (2, 10)], false));
+        assert_eq!(eye_values((None, None, Some(30)), &[1, 2]), (vec![(2, 30)], false));
+        // One unit takes the shared value, flagging per-eye values that differ
+        assert_eq!(eye_values((Some(10), Some(20), Some(30)), &[1]), (vec![(1, 10)], true));
+        assert_eq!(eye_values((None, Some(20), Some(20)), &[1]), (vec![(1, 20)], false));
+        assert_eq!(eye_values((None, Some(20), None), &[1]), (vec![(1, 20)], true));
+        assert_eq!(eye_values((Some(10), None, None), &[]), (vec![], false));
     }
-}
\ No newline at end of file
+}
@@ -1,23 +1,152 @@
 // Licensed under the Apache License
 // SPDX-License-Identifier: Apache-2.0
 
+use std::sync::Arc;
+
 use gst::glib;
 use gst::prelude::*;
+use gst::subclass::prelude::*;
 
+mod config;
 mod imp;
+mod process;
+#[cfg(target_os = "linux")]
+mod sysfs;
+
+// Output format of the element
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstBigEyeSrcFormat")]
+pub enum Format {
+    #[enum_value(name = "MJPEG: Motion JPEG as sent by the camera", nick = "mjpeg")]
+    Mjpeg = 0,
+    #[enum_value(name = "Raw: Uncompressed YUY2", nick = "raw")]
+    Raw = 1,
+    // Replaced by one of the others in start()
+    #[enum_value(
+        name = "Auto: Raw when the USB link of the camera is fast enough for it, MJPEG otherwise",
+        nick = "auto"
+    )]
+    Auto = 2,
+}
+
+// What to do when a frame is corrupt or the camera stalls
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstBigEyeSrcStallAction")]
+pub enum StallAction {
+    #[enum_value(name = "Drop: Skip the frame and keep waiting", nick = "drop")]
+    Drop = 0,
+    #[enum_value(name = "Gap: Push a GAP event for the missing frame", nick = "gap")]
+    Gap = 1,
+    #[enum_value(
+        name = "Repeat last: Push the last good frame again",
+        nick = "repeat-last"
+    )]
+    RepeatLast = 2,
+}
+
+// What to do once the camera is found to repeat the same frame
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstBigEyeSrcFreezeAction")]
+pub enum FreezeAction {
+    #[enum_value(name = "Warn: Post a warning message", nick = "warn")]
+    Warn = 0,
+    #[enum_value(name = "Reset: Post a warning and reopen the device", nick = "reset")]
+    Reset = 1,
+}
+
+// How the IR illuminator lights the eyes, on firmware that can switch
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstBigEyeSrcIlluminatorMode")]
+pub enum IlluminatorMode {
+    #[enum_value(name = "Default: Leave the mode the firmware starts with", nick = "default")]
+    Default = 0,
+    #[enum_value(name = "Flood: Even flood illumination", nick = "flood")]
+    Flood = 1,
+    #[enum_value(name = "Pattern: Structured light from the dot projector", nick = "pattern")]
+    Pattern = 2,
+}
+
+// What the timestamps of the pushed buffers count from
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstBigEyeSrcPtsBase")]
+pub enum PtsBase {
+    #[enum_value(name = "Running time: Running time of the pipeline when the frame is pushed", nick = "running-time")]
+    RunningTime = 0,
+    #[enum_value(name = "Zero: Running time minus that of the first buffer, which gets PTS 0", nick = "zero")]
+    Zero = 1,
+}
 
 // The public Rust wrapper type for our element
 glib::wrapper! {
     pub struct BigEyeSrc(ObjectSubclass<imp::BigEyeSrc>) @extends gst_base::PushSrc, gst_base::BaseSrc, gst::Element, gst::Object;
 }
 
+// Timing of a frame the element is about to push, passed to the hook set
+// with `set_frame_hook()`. Times are host monotonic time as returned by
+// `gst::get_timestamp()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTiming {
+    // Frame sequence number as reported by libuvc
+    pub sequence: u32,
+    pub width: u32,
+    pub height: u32,
+    // When the frame finished arriving from the camera
+    pub capture_time: gst::ClockTime,
+    // When create() took it from the queue
+    pub dequeue_time: gst::ClockTime,
+    // PTS of the buffer, None without a clock
+    pub pts: Option<gst::ClockTime>,
+}
+
+type FrameHook = dyn Fn(&FrameTiming) + Send + Sync;
+
+// Installs a hook called with the timing of every frame `element` pushes,
+// replacing the previous one, for Rust code linking the crate that wants
+// instrumentation without GObject signals. It runs on the element's
+// streaming thread right before the buffer is pushed, so it delays every
+// frame by however long it takes. Without a hook nothing is computed.
+pub fn set_frame_hook(
+    element: &gst::Element,
+    hook: impl Fn(&FrameTiming) + Send + Sync + 'static,
+) -> Result<(), glib::BoolError> {
+    set_hook(element, Some(Arc::new(hook)))
+}
+
+// Removes the hook installed with `set_frame_hook()`
+pub fn clear_frame_hook(element: &gst::Element) -> Result<(), glib::BoolError> {
+    set_hook(element, None)
+}
+
+fn set_hook(element: &gst::Element, hook: Option<Arc<FrameHook>>) -> Result<(), glib::BoolError> {
+    let element = element
+        .downcast_ref::<BigEyeSrc>()
+        .ok_or_else(|| glib::bool_error!("{} is not a bigeyesrc", element.name()))?;
+    element.imp().set_frame_hook(hook);
+    Ok(())
+}
+
+// Name the element is registered under unless BIGEYESRC_ELEMENT_NAME is
+// set when the plugin is loaded, the GType stays BigEyeSrc either way
+const DEFAULT_ELEMENT_NAME: &str = "bigeyesrc";
+
 // Registers the type for our element, and then registers in GStreamer under
-// the name "BigEyeSrc" for being able to instantiate it via e.g.
+// the element name for being able to instantiate it via e.g.
 // gst::ElementFactory::make().
 pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    crate::meta::register();
+
+    let name = std::env::var("BIGEYESRC_ELEMENT_NAME")
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_ELEMENT_NAME.to_string());
     gst::Element::register(
         Some(plugin),
-        "bigeyesrc",
+        &name,
         gst::Rank::NONE,
         BigEyeSrc::static_type(),
     )
@@ -0,0 +1,182 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// Pixel operations applied to raw (YUY2) frames before they are pushed.
+// In YUY2 every even byte is a luma sample, odd bytes are chroma.
+
+// Inverts the luma of a YUY2 frame in place
+pub fn invert_yuy2(data: &mut [u8]) {
+    for y in data.iter_mut().step_by(2) {
+        *y = 255 - *y;
+    }
+}
+
+// FNV-1a hash over the length and 256 evenly spaced bytes of a frame. Good
+// enough to tell whether the camera keeps sending the exact same frame
+// without a full pass over the data.
+pub fn sparse_hash(data: &[u8]) -> u64 {
+    const SAMPLES: usize = 256;
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let mut hash = OFFSET_BASIS;
+    for byte in (data.len() as u64).to_le_bytes() {
+        hash = (hash ^ byte as u64).wrapping_mul(PRIME);
+    }
+    let step = (data.len() / SAMPLES).max(1);
+    for &byte in data.iter().step_by(step) {
+        hash = (hash ^ byte as u64).wrapping_mul(PRIME);
+    }
+    hash
+}
+
+// Mean luma of the left and right half of a YUY2 frame. Only every 8th
+// pixel of every 8th row is sampled to keep this cheap.
+pub fn mean_luma_halves_yuy2(data: &[u8], width: usize, height: usize) -> (f64, f64) {
+    const STEP: usize = 8;
+
+    let stride = width * 2;
+    let half = width / 2;
+    let (mut left, mut right, mut count) = (0u64, 0u64, 0u64);
+    for row in data.chunks_exact(stride).take(height).step_by(STEP) {
+        for x in (0..half).step_by(STEP) {
+            left += row[x * 2] as u64;
+            right += row[(half + x) * 2] as u64;
+        }
+        count += half.div_ceil(STEP) as u64;
+    }
+
+    if count == 0 {
+        return (0.0, 0.0);
+    }
+    (left as f64 / count as f64, right as f64 / count as f64)
+}
+
+// Stretches the luma of a YUY2 frame in place so it covers the full 0-255 range
+pub fn normalize_yuy2(data: &mut [u8]) {
+    let (min, max) = data
+        .iter()
+        .step_by(2)
+        .fold((u8::MAX, u8::MIN), |(min, max), &y| (min.min(y), max.max(y)));
+
+    // Nothing to stretch in an empty or flat frame
+    if min >= max {
+        return;
+    }
+
+    let range = (max - min) as u32;
+    for y in data.iter_mut().step_by(2) {
+        *y = ((*y - min) as u32 * 255 / range) as u8;
+    }
+}
+
+// Luma of a tightly packed YUY2 frame as GRAY8 in rows of `stride` bytes
+pub fn luma_yuy2(data: &[u8], (width, height): (usize, usize), stride: usize) -> Vec<u8> {
+    let mut out = vec![0; stride * height];
+    for (row, src_row) in out.chunks_exact_mut(stride).zip(data.chunks_exact(width * 2)) {
+        for (y, pixel) in row.iter_mut().zip(src_row.iter().step_by(2)) {
+            *y = *pixel;
+        }
+    }
+    out
+}
+
+// Nearest neighbour resize of a tightly packed YUY2 frame into rows of
+// `stride` bytes. Chroma is taken from the macropixel the source pixel
+// belongs to, so odd output widths work as well. The last pixel of an odd
+// source width has no macropixel of its own and takes the one before it.
+// A frame without a whole macropixel or shorter than its size gives an
+// empty picture.
+pub fn scale_yuy2(
+    data: &[u8],
+    (src_width, src_height): (usize, usize),
+    (width, height): (usize, usize),
+    stride: usize,
+) -> Vec<u8> {
+    let mut out = vec![0; stride * height];
+    let src_stride = src_width * 2;
+    if src_width < 2 || src_height == 0 || data.len() < src_stride * src_height {
+        return out;
+    }
+
+    // Start of the last whole macropixel in a row
+    let last_macropixel = (src_width - 2) & !1;
+    for (y, row) in out.chunks_exact_mut(stride).enumerate() {
+        let src_row = &data[(y * src_height / height) * src_stride..][..src_stride];
+        for x in 0..width {
+            let src_x = x * src_width / width;
+            let macropixel = &src_row[(src_x & !1).min(last_macropixel) * 2..][..4];
+            row[x * 2] = src_row[src_x * 2];
+            // U on even, V on odd output pixels
+            row[x * 2 + 1] = macropixel[1 + (x & 1) * 2];
+        }
+    }
+    out
+}
+
+// Red/cyan anaglyph of a tightly packed YUY2 frame with the left eye in its
+// left half and the right eye in its right half. The luma of the left eye
+// becomes red, that of the right eye green and blue. The result is tightly
+// packed YUY2 of half the width, rounded down to whole macropixels.
+pub fn anaglyph_yuy2(data: &[u8], (width, height): (usize, usize)) -> Vec<u8> {
+    let eye_width = width / 4 * 2;
+    // Byte offset of the right eye in a row, lumas sit at every even byte
+    let right_eye = width / 2 * 2;
+    let mut out = vec![0; eye_width * 2 * height];
+    for (src, dst) in data.chunks_exact(width * 2).zip(out.chunks_exact_mut(eye_width * 2)) {
+        for (x, macropixel) in dst.chunks_exact_mut(4).enumerate() {
+            let (mut u, mut v) = (0, 0);
+            for i in 0..2 {
+                let left = i32::from(src[x * 4 + i * 2]);
+                let right = i32::from(src[right_eye + x * 4 + i * 2]);
+                // BT.601 of (left, right, right)
+                macropixel[i * 2] = ((299 * left + 701 * right + 500) / 1000) as u8;
+                u += 169 * (right - left);
+                v += 500 * (left - right);
+            }
+            macropixel[1] = (128 + u / 2000).clamp(0, 255) as u8;
+            macropixel[3] = (128 + v / 2000).clamp(0, 255) as u8;
+        }
+    }
+    out
+}
+
+// Places a tightly packed YUY2 frame in the middle of a larger one in rows
+// of `stride` bytes, filling the bars with `fill` luma and neutral chroma.
+// The picture starts on an even pixel so its macropixels stay whole.
+pub fn letterbox_yuy2(
+    data: &[u8],
+    (src_width, src_height): (usize, usize),
+    (width, height): (usize, usize),
+    stride: usize,
+    fill: u8,
+) -> Vec<u8> {
+    let mut out = [fill, 128].repeat(stride.div_ceil(2) * height);
+    out.truncate(stride * height);
+    let (left, top) = letterbox_origin((src_width, src_height), (width, height));
+
+    let src_stride = src_width * 2;
+    for (row, src_row) in out.chunks_exact_mut(stride).skip(top).zip(data.chunks_exact(src_stride)) {
+        row[left * 2..][..src_stride].copy_from_slice(src_row);
+    }
+    out
+}
+
+// Top left corner of the picture placed by letterbox_yuy2()
+pub fn letterbox_origin((src_width, src_height): (usize, usize), (width, height): (usize, usize)) -> (usize, usize) {
+    (((width - src_width) / 2) & !1, (height - src_height) / 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_yuy2_checks_the_frame() {
+        // Three pixels, the last one without a V of its own
+        let odd = [10, 1, 20, 2, 30, 3];
+        assert_eq!(scale_yuy2(&odd, (3, 1), (3, 1), 6), [10, 1, 20, 2, 30, 1]);
+        // One row short
+        assert_eq!(scale_yuy2(&odd, (3, 2), (2, 2), 4), [0; 8]);
+    }
+}
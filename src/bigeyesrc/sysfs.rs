@@ -0,0 +1,26 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// Access to the Linux sysfs attributes of the camera's USB device
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::capture::usb;
+
+// Sysfs directory of the USB device with the given bus number and address
+pub fn usb_device_path(bus: u8, address: u8) -> Option<PathBuf> {
+    usb::devices().find(|dir| usb::address(dir) == Some((bus, address)))
+}
+
+// Writes `value` to the device's power/control attribute and returns the
+// previous value
+pub fn set_power_control(device: &Path, value: &str) -> io::Result<String> {
+    let control = device.join("power").join("control");
+    let previous = fs::read_to_string(&control)?.trim().to_string();
+    if previous != value {
+        fs::write(&control, value)?;
+    }
+    Ok(previous)
+}
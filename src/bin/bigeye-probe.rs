@@ -0,0 +1,87 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// Checks that the camera can be opened and prints what it reports, or why
+// it can't be opened
+//
+//   bigeye-probe [--serial SERIAL] [--install-udev-rule]
+
+use std::process::ExitCode;
+
+use gstbigeye::capture::{self, Camera, Selector};
+
+fn main() -> ExitCode {
+    let mut selector = Selector::default();
+    let mut install_udev_rule = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--serial" => selector.serial = args.next(),
+            "--install-udev-rule" => install_udev_rule = true,
+            _ => {
+                eprintln!("Usage: bigeye-probe [--serial SERIAL] [--install-udev-rule]");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if install_udev_rule {
+        #[cfg(target_os = "linux")]
+        match capture::access::install_udev_rule() {
+            Ok(path) => {
+                println!("Installed {}, replug the camera or run: udevadm control --reload && udevadm trigger", path.display());
+            }
+            Err(err) => {
+                eprintln!("Could not install the udev rule, try again as root: {}", err);
+                return ExitCode::FAILURE;
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            eprintln!("udev rules only exist on Linux");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let camera = match Camera::open(&selector) {
+        Ok(camera) => camera,
+        Err(e) => {
+            eprintln!("{}", e);
+            #[cfg(target_os = "linux")]
+            if e.uvc_error() == Some(uvc::Error::Access)
+                && let Some(diagnosis) = capture::access::diagnose(selector.serial.as_deref())
+            {
+                eprintln!("{}", diagnosis);
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("Serial number: {}", camera.serial().unwrap_or("unknown"));
+    if let Some((bus, address)) = camera.usb_address() {
+        println!("USB bus {} device {}", bus, address);
+    }
+    println!("Frame sizes: {:?}", camera.sizes());
+    if let Some(probe) = camera.probe() {
+        println!("Controls: {}", probe.controls.join(", "));
+    }
+    if let Some(mode) = camera.mode() {
+        println!(
+            "Streaming {}x{}@{} {}",
+            mode.width,
+            mode.height,
+            mode.fps,
+            String::from_utf8_lossy(&mode.fourcc)
+        );
+    }
+    match camera.try_next(std::time::Duration::from_secs(1)) {
+        Some(frame) => println!("Received frame {} of {} bytes", frame.sequence(), frame.bytes().len()),
+        None => {
+            eprintln!("No frame within a second");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
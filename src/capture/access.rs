@@ -0,0 +1,186 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// Why the camera's USB device node can't be opened, for turning an Access
+// error into something the user can act on
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+
+use super::usb;
+
+const UDEV_RULE_DIRS: [&str; 4] = [
+    "/etc/udev/rules.d",
+    "/run/udev/rules.d",
+    "/usr/lib/udev/rules.d",
+    "/lib/udev/rules.d",
+];
+
+// Rule granting the logged in user and the plugdev group access to the camera
+pub const UDEV_RULE: &str = r#"SUBSYSTEM=="usb", ATTR{idVendor}=="35bd", ATTR{idProduct}=="0202", MODE="0660", GROUP="plugdev", TAG+="uaccess""#;
+
+// Where `install_udev_rule()` puts the rule
+pub const UDEV_RULE_PATH: &str = "/etc/udev/rules.d/70-bigeye.rules";
+
+// What was found out about the access to the camera's device node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis {
+    // The /dev/bus/usb node of the camera
+    pub node: PathBuf,
+    pub owner: String,
+    pub group: String,
+    // Permission bits of the node
+    pub mode: u32,
+    // Whether this process is in the plugdev group, None if there is none
+    pub in_plugdev: Option<bool>,
+    // udev rule files that mention the vendor ID of the camera
+    pub udev_rules: Vec<PathBuf>,
+}
+
+impl Diagnosis {
+    // What to do about it, including the udev rule to install if none exists
+    pub fn suggestion(&self) -> String {
+        if self.udev_rules.is_empty() {
+            format!(
+                "No udev rule for the camera was found, add this line to {} and replug the camera (or run bigeye-probe --install-udev-rule): {}",
+                UDEV_RULE_PATH, UDEV_RULE
+            )
+        } else if self.in_plugdev == Some(false) && self.group == "plugdev" {
+            "The device belongs to the plugdev group, add your user to it and log in again".to_string()
+        } else {
+            format!(
+                "A udev rule exists but doesn't grant access, check that it matches and replug the camera, or use this rule: {}",
+                UDEV_RULE
+            )
+        }
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is owned by {}:{} with mode {:04o}",
+            self.node.display(),
+            self.owner,
+            self.group,
+            self.mode & 0o7777
+        )?;
+        match self.in_plugdev {
+            Some(true) => write!(f, ", this process is in plugdev")?,
+            Some(false) => write!(f, ", this process is not in plugdev")?,
+            None => {}
+        }
+        if self.udev_rules.is_empty() {
+            write!(f, ", no udev rule mentions vendor 35bd")?;
+        } else {
+            let rules = self
+                .udev_rules
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>();
+            write!(f, ", udev rules mentioning vendor 35bd: {}", rules.join(", "))?;
+        }
+        write!(f, ". {}", self.suggestion())
+    }
+}
+
+// Looks up the camera with the given serial number, or the first one, and
+// inspects its device node. None if it isn't connected.
+pub fn diagnose(serial: Option<&str>) -> Option<Diagnosis> {
+    let (bus, address) = find_camera(serial)?;
+    let node = PathBuf::from(format!("/dev/bus/usb/{:03}/{:03}", bus, address));
+    let metadata = fs::metadata(&node).ok()?;
+
+    let plugdev = group_id("plugdev");
+    let groups = process_groups();
+    Some(Diagnosis {
+        owner: user_name(metadata.uid()).unwrap_or_else(|| metadata.uid().to_string()),
+        group: group_name(metadata.gid()).unwrap_or_else(|| metadata.gid().to_string()),
+        mode: metadata.mode(),
+        in_plugdev: plugdev.map(|gid| groups.contains(&gid)),
+        udev_rules: udev_rules(),
+        node,
+    })
+}
+
+// Writes `UDEV_RULE` to `UDEV_RULE_PATH`, which usually requires root
+pub fn install_udev_rule() -> io::Result<PathBuf> {
+    fs::write(UDEV_RULE_PATH, format!("# Bigscreen Beyond 2e eye tracking camera\n{}\n", UDEV_RULE))?;
+    Ok(PathBuf::from(UDEV_RULE_PATH))
+}
+
+// Bus number and address of the camera from sysfs
+fn find_camera(serial: Option<&str>) -> Option<(u8, u8)> {
+    let read = usb::attribute;
+    usb::devices()
+        .filter(|dir| {
+            read(dir, "idVendor").as_deref() == Some("35bd")
+                && read(dir, "idProduct").as_deref() == Some("0202")
+                && serial.is_none_or(|serial| read(dir, "serial").as_deref() == Some(serial))
+        })
+        .find_map(|dir| usb::address(&dir))
+}
+
+// Fields of the first /etc/passwd or /etc/group entry that `matches`
+fn database_entry(path: &str, matches: impl Fn(&[&str]) -> bool) -> Option<Vec<String>> {
+    fs::read_to_string(path)
+        .ok()?
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.len() >= 3 && matches(fields))
+        .map(|fields| fields.into_iter().map(String::from).collect())
+}
+
+fn user_name(uid: u32) -> Option<String> {
+    let entry = database_entry("/etc/passwd", |fields| fields[2] == uid.to_string())?;
+    entry.into_iter().next()
+}
+
+fn group_name(gid: u32) -> Option<String> {
+    let entry = database_entry("/etc/group", |fields| fields[2] == gid.to_string())?;
+    entry.into_iter().next()
+}
+
+fn group_id(name: &str) -> Option<u32> {
+    let entry = database_entry("/etc/group", |fields| fields[0] == name)?;
+    entry[2].parse().ok()
+}
+
+// Supplementary and effective group IDs of this process
+fn process_groups() -> Vec<u32> {
+    let Ok(status) = fs::read_to_string("/proc/self/status") else {
+        return Vec::new();
+    };
+    status
+        .lines()
+        .filter_map(|line| {
+            if let Some(groups) = line.strip_prefix("Groups:") {
+                Some(groups.split_whitespace().filter_map(|gid| gid.parse().ok()).collect())
+            } else {
+                // Real, effective, saved and filesystem group ID
+                let gids = line.strip_prefix("Gid:")?;
+                Some(gids.split_whitespace().nth(1)?.parse().ok().into_iter().collect::<Vec<_>>())
+            }
+        })
+        .flatten()
+        .collect()
+}
+
+// Rule files that mention the vendor ID of the camera
+fn udev_rules() -> Vec<PathBuf> {
+    UDEV_RULE_DIRS
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "rules")
+                && fs::read_to_string(path).is_ok_and(|rules| rules.to_lowercase().contains("35bd"))
+        })
+        .collect()
+}
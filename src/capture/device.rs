@@ -0,0 +1,441 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// Opening the camera through libuvc, or the mock backend in its place
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "mock")]
+use std::time::Duration;
+
+#[cfg(feature = "mock")]
+use super::mock;
+use super::{Error, Frame, Mode, PixelFormat, Probe, Selector, Shared};
+#[cfg(feature = "mock")]
+use super::TestPattern;
+
+// The libuvc objects of an open camera. Each borrows from the one before
+// it, so they live on the heap and are released in reverse order on drop.
+pub struct DeviceStack {
+    stream: Option<uvc::ActiveStream<'static, Arc<Shared>>>,
+    #[cfg(feature = "mock")]
+    mock: Option<mock::MockStream>,
+    // Pattern, size and frame rate of the mock stream, for restarting it
+    #[cfg(feature = "mock")]
+    mock_mode: Option<(TestPattern, (u32, u32), u32)>,
+    // Selector::affinity_mask, applied again to a restarted stream
+    affinity_mask: u64,
+    streamh: *mut uvc::StreamHandle<'static>,
+    devh: *mut uvc::DeviceHandle<'static>,
+    dev: *mut uvc::Device<'static>,
+    ctx: *mut uvc::Context<'static>,
+}
+
+// SAFETY: the pointers are only dereferenced while the stack is alive and
+// all of the libuvc wrapper types are Send + Sync themselves
+unsafe impl Send for DeviceStack {}
+unsafe impl Sync for DeviceStack {}
+
+impl Default for DeviceStack {
+    fn default() -> Self {
+        DeviceStack {
+            stream: None,
+            #[cfg(feature = "mock")]
+            mock: None,
+            #[cfg(feature = "mock")]
+            mock_mode: None,
+            affinity_mask: 0,
+            streamh: std::ptr::null_mut(),
+            devh: std::ptr::null_mut(),
+            dev: std::ptr::null_mut(),
+            ctx: std::ptr::null_mut(),
+        }
+    }
+}
+
+// The mock backend only uses the stream slot
+#[cfg_attr(feature = "mock", allow(dead_code))]
+impl DeviceStack {
+    pub fn device(&self) -> Option<&uvc::Device<'static>> {
+        unsafe { self.dev.as_ref() }
+    }
+
+    pub fn handle(&self) -> Option<&uvc::DeviceHandle<'static>> {
+        unsafe { self.devh.as_ref() }
+    }
+
+    pub fn stop_stream(&mut self) {
+        drop(self.stream.take());
+        #[cfg(feature = "mock")]
+        drop(self.mock.take());
+    }
+
+    fn set_context(&mut self, ctx: uvc::Context<'static>) -> &'static uvc::Context<'static> {
+        self.ctx = Box::into_raw(Box::new(ctx));
+        unsafe { &*self.ctx }
+    }
+
+    fn set_device(&mut self, dev: uvc::Device<'static>) -> &'static uvc::Device<'static> {
+        self.dev = Box::into_raw(Box::new(dev));
+        unsafe { &*self.dev }
+    }
+
+    fn set_handle(&mut self, devh: uvc::DeviceHandle<'static>) -> &'static uvc::DeviceHandle<'static> {
+        self.devh = Box::into_raw(Box::new(devh));
+        unsafe { &*self.devh }
+    }
+
+    fn set_stream_handle(
+        &mut self,
+        streamh: uvc::StreamHandle<'static>,
+    ) -> &'static mut uvc::StreamHandle<'static> {
+        self.streamh = Box::into_raw(Box::new(streamh));
+        unsafe { &mut *self.streamh }
+    }
+}
+
+impl DeviceStack {
+    // Stops the stream and starts it again on the same stream handle, the
+    // device stays open
+    #[cfg(not(feature = "mock"))]
+    pub fn restart_stream(&mut self, shared: &Arc<Shared>) -> Result<(), Error> {
+        self.stop_stream();
+        if self.streamh.is_null() {
+            return Err(Error::Stream(uvc::Error::InvalidDevice));
+        }
+        // SAFETY: the stream that borrowed the handle was just dropped
+        let streamh = unsafe { &mut *self.streamh };
+        self.stream = Some(start_stream(streamh, shared, self.affinity_mask)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "mock")]
+    pub fn restart_stream(&mut self, shared: &Arc<Shared>) -> Result<(), Error> {
+        self.stop_stream();
+        let (pattern, size, fps) = self.mock_mode.ok_or(Error::Stream(uvc::Error::InvalidDevice))?;
+        self.mock = Some(start_mock(pattern, size, fps, shared, self.affinity_mask));
+        Ok(())
+    }
+}
+
+impl Drop for DeviceStack {
+    fn drop(&mut self) {
+        // Stop streaming before anything the stream borrows goes away
+        self.stop_stream();
+
+        // SAFETY: every pointer was created by Box::into_raw in a setter and
+        // nothing borrowing from it is left once the later ones are freed
+        unsafe {
+            if !self.streamh.is_null() {
+                drop(Box::from_raw(self.streamh));
+            }
+            if !self.devh.is_null() {
+                drop(Box::from_raw(self.devh));
+            }
+            if !self.dev.is_null() {
+                drop(Box::from_raw(self.dev));
+            }
+            if !self.ctx.is_null() {
+                drop(Box::from_raw(self.ctx));
+            }
+        }
+    }
+}
+
+// A streaming device and what was learned about it while opening
+pub struct Opened {
+    pub device: DeviceStack,
+    pub sizes: Vec<(u32, u32)>,
+    pub serial: Option<String>,
+    pub mode: Option<Mode>,
+}
+
+// Frame sizes the descriptors of the streaming interface list for the
+// given format
+#[cfg_attr(feature = "mock", allow(dead_code))]
+fn supported_sizes(
+    devh: &uvc::DeviceHandle,
+    format: PixelFormat,
+    interface_number: u8,
+) -> Result<Vec<(u32, u32)>, Error> {
+    let format = match format {
+        PixelFormat::Mjpeg => uvc::FrameFormat::MJPEG,
+        PixelFormat::Yuy2 => uvc::FrameFormat::YUYV,
+    };
+    devh.frame_sizes_on_interface(format, interface_number).map_err(Error::Interfaces)
+}
+
+// Reads the descriptors and controls of an opened device
+#[cfg(not(feature = "mock"))]
+pub fn describe(device: &DeviceStack) -> Option<Probe> {
+    let (dev, devh) = (device.device()?, device.handle()?);
+    let description = dev.description().ok();
+
+    let mut modes = Vec::new();
+    for format_desc in devh.supported_formats() {
+        for frame_desc in format_desc.supported_formats() {
+            let fourcc = match frame_desc.subtype() {
+                uvc::DescriptionSubtype::FrameMJPEG => *b"MJPG",
+                uvc::DescriptionSubtype::FrameUncompressed => *b"YUY2",
+                _ => continue,
+            };
+            // Some devices report a zero interval, which has no frame rate
+            let rates = frame_desc.intervals().iter().filter_map(|&interval| 10_000_000u32.checked_div(interval));
+            for fps in rates {
+                modes.push(Mode {
+                    width: frame_desc.width().into(),
+                    height: frame_desc.height().into(),
+                    fps,
+                    fourcc,
+                });
+            }
+        }
+    }
+
+    let controls = [
+        ("scanning-mode", devh.scanning_mode().is_ok()),
+        ("ae-mode", devh.ae_mode().is_ok()),
+        ("ae-priority", devh.ae_priority().is_ok()),
+        ("exposure-abs", devh.exposure_abs().is_ok()),
+        ("exposure-rel", devh.exposure_rel().is_ok()),
+        ("focus-abs", devh.focus_abs().is_ok()),
+        ("focus-rel", devh.focus_rel().is_ok()),
+    ]
+    .into_iter()
+    .filter_map(|(name, readable)| readable.then_some(name))
+    .collect();
+
+    Some(Probe {
+        serial: description.as_ref().and_then(|d| d.serial_number.clone()),
+        manufacturer: description.as_ref().and_then(|d| d.manufacturer.clone()),
+        product: description.as_ref().and_then(|d| d.product.clone()),
+        uvc_version: description.as_ref().map_or(0, |d| d.bcd_uvc),
+        usb_address: (dev.bus_number(), dev.device_address()),
+        usb_speed: Some(devh.usb_speed()),
+        modes,
+        controls,
+    })
+}
+
+// Opens the device only for as long as it takes to describe it
+#[cfg(not(feature = "mock"))]
+pub fn probe(serial: Option<&str>) -> Result<Probe, Error> {
+    let mut device = DeviceStack::default();
+    let ctx = device.set_context(uvc::Context::new().map_err(Error::Context)?);
+    let dev = device.set_device(
+        ctx.find_device(Some(super::VENDOR_ID as i32), Some(super::PRODUCT_ID as i32), serial)
+            .map_err(Error::NotFound)?,
+    );
+    device.set_handle(dev.open().map_err(Error::Open)?);
+    // Both are set, so describe() can't fail
+    Ok(describe(&device).expect("device is open"))
+}
+
+// The mock camera and its modes
+#[cfg(feature = "mock")]
+pub fn describe(_device: &DeviceStack) -> Option<Probe> {
+    Some(Probe {
+        serial: None,
+        manufacturer: None,
+        product: Some("Mock camera".to_string()),
+        uvc_version: 0x0110,
+        usb_address: (0, 0),
+        usb_speed: None,
+        modes: MOCK_MODES.to_vec(),
+        controls: Vec::new(),
+    })
+}
+
+#[cfg(feature = "mock")]
+pub fn probe(_serial: Option<&str>) -> Result<Probe, Error> {
+    Ok(describe(&DeviceStack::default()).expect("mock is always described"))
+}
+
+// Starts the stream with a callback that queues the frame data
+#[cfg(not(feature = "mock"))]
+fn start_stream(
+    streamh: &'static mut uvc::StreamHandle<'static>,
+    shared: &Arc<Shared>,
+    affinity_mask: u64,
+) -> Result<uvc::ActiveStream<'static, Arc<Shared>>, Error> {
+    let pinned = AtomicBool::new(false);
+    streamh
+        .start_stream(
+            move |frame, shared| {
+                // libuvc calls back on a thread of its own per stream
+                pin_once(&pinned, affinity_mask);
+                shared.push(Frame {
+                    data: frame.to_bytes().to_vec(),
+                    width: frame.width(),
+                    height: frame.height(),
+                    sequence: frame.sequence(),
+                    capture_time: frame.capture_time_finished(),
+                });
+            },
+            shared.clone(),
+        )
+        .map_err(Error::Stream)
+}
+
+// Pins the thread delivering frames on its first frame. There is no one to
+// report a failure to here, the element pins its own thread to the same
+// mask and warns when that fails.
+fn pin_once(pinned: &AtomicBool, affinity_mask: u64) {
+    if affinity_mask != 0 && !pinned.swap(true, Ordering::Relaxed) {
+        let _ = super::pin_thread(affinity_mask);
+    }
+}
+
+// Opens the camera and starts streaming into `shared`
+// This initializes the UVC context, then gets the device, opens it, creates the stream, and then starts it
+// Each step is kept in a DeviceStack, so an error part way through releases
+// everything that was set up before it.
+#[cfg(not(feature = "mock"))]
+pub fn open(selector: &Selector, shared: &Arc<Shared>) -> Result<Opened, Error> {
+    let mut device = DeviceStack::default();
+
+    // Initialize context
+    let ctx = device.set_context(uvc::Context::new().map_err(Error::Context)?);
+
+    // Get a BSB2E device using Vendor ID and Product ID, and the serial
+    // number if one was selected
+    let dev = device.set_device(
+        ctx.find_device(
+            Some(super::VENDOR_ID as i32),
+            Some(super::PRODUCT_ID as i32),
+            selector.serial.as_deref(),
+        )
+        .map_err(Error::NotFound)?,
+    );
+    let serial = dev
+        .description()
+        .ok()
+        .and_then(|description| description.serial_number);
+
+    // Open the device
+    let devh = device.set_handle(dev.open().map_err(Error::Open)?);
+
+    // Resolve the requested streaming interface index to its interface number
+    let interfaces = devh.streaming_interfaces().map_err(Error::Interfaces)?;
+    let interface_number = *interfaces
+        .get(selector.interface as usize)
+        .ok_or(Error::NoInterface {
+            index: selector.interface,
+            available: interfaces.len(),
+        })?;
+
+    let sizes = supported_sizes(devh, selector.format, interface_number)?;
+
+    // Configure for the requested format, size and frame rate
+    let format = uvc::StreamFormat {
+        width: selector.width,
+        height: selector.height,
+        fps: selector.fps,
+        format: match selector.format {
+            PixelFormat::Mjpeg => uvc::FrameFormat::MJPEG,
+            PixelFormat::Yuy2 => uvc::FrameFormat::YUYV,
+        },
+    };
+
+    // Get stream handle
+    let streamh = device.set_stream_handle(
+        devh.get_stream_handle_with_format_on_interface(format, interface_number)
+            .map_err(Error::Format)?,
+    );
+    let mode = streamh.negotiated_format().map(|format| Mode {
+        width: format.width,
+        height: format.height,
+        fps: format.fps,
+        fourcc: match format.format {
+            uvc::FrameFormat::MJPEG => *b"MJPG",
+            // Only YUY2 is ever requested uncompressed
+            _ => *b"YUY2",
+        },
+    });
+
+    // Dropping the partially built stack closes the handle, device and
+    // context again if this fails
+    device.affinity_mask = selector.affinity_mask;
+    device.stream = Some(start_stream(streamh, shared, device.affinity_mask)?);
+
+    Ok(Opened {
+        device,
+        sizes,
+        serial,
+        mode,
+    })
+}
+
+// Generates frames of the test pattern into `shared`
+#[cfg(feature = "mock")]
+fn start_mock(
+    pattern: TestPattern,
+    (width, height): (u32, u32),
+    fps: u32,
+    shared: &Arc<Shared>,
+    affinity_mask: u64,
+) -> mock::MockStream {
+    let shared = shared.clone();
+    let pinned = AtomicBool::new(false);
+    mock::MockStream::start(pattern, (width as usize, height as usize), fps, move |data, sequence| {
+        pin_once(&pinned, affinity_mask);
+        shared.push(Frame {
+            data,
+            width,
+            height,
+            sequence,
+            capture_time: Duration::from_nanos(gst::get_timestamp().nseconds()),
+        });
+    })
+}
+
+// Modes of the mock camera, the native one first, and a smaller and
+// slower one to negotiate away from it
+#[cfg(feature = "mock")]
+const MOCK_MODES: [Mode; 2] = [
+    Mode {
+        width: super::WIDTH,
+        height: super::HEIGHT,
+        fps: super::FPS,
+        fourcc: *b"YUY2",
+    },
+    Mode {
+        width: super::WIDTH / 2,
+        height: super::HEIGHT / 2,
+        fps: 60,
+        fourcc: *b"YUY2",
+    },
+];
+
+// Starts generating test-pattern frames into `shared` in place of the
+// camera, at whichever size and frame rate is selected. Only YUY2 is
+// offered.
+#[cfg(feature = "mock")]
+pub fn open(selector: &Selector, shared: &Arc<Shared>) -> Result<Opened, Error> {
+    if selector.format != PixelFormat::Yuy2 {
+        return Err(Error::MockFormat);
+    }
+
+    let (width, height) = (selector.width, selector.height);
+    let mut device = DeviceStack::default();
+    device.mock_mode = Some((selector.test_pattern, (width, height), selector.fps));
+    device.affinity_mask = selector.affinity_mask;
+    device.mock = Some(start_mock(
+        selector.test_pattern,
+        (width, height),
+        selector.fps,
+        shared,
+        device.affinity_mask,
+    ));
+    Ok(Opened {
+        device,
+        sizes: MOCK_MODES.iter().map(|mode| (mode.width, mode.height)).collect(),
+        serial: None,
+        mode: Some(Mode {
+            width,
+            height,
+            fps: selector.fps,
+            fourcc: *b"YUY2",
+        }),
+    })
+}
@@ -0,0 +1,105 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// Stand-in for the camera with the `mock` feature: a thread that produces
+// deterministic raw (YUY2) frames, so the element can be exercised without
+// hardware
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use super::TestPattern;
+
+// Side length of the moving-box pattern's box and of the eye markers
+const BOX_SIZE: usize = 64;
+const MARKER_SIZE: usize = 16;
+
+// Generates the YUY2 frame with the given sequence number. Chroma is always
+// neutral, so only the luma (even bytes) carries the pattern. All but the
+// moving box and noise repeat the same payload in every frame, like a
+// frozen camera, while noise never does.
+pub fn generate(pattern: TestPattern, (width, height): (usize, usize), sequence: u32) -> Vec<u8> {
+    let luma = |x: usize, y: usize| -> u8 {
+        match pattern {
+            TestPattern::Solid => 128,
+            TestPattern::Gradient => (x * 255 / width.saturating_sub(1).max(1)) as u8,
+            TestPattern::MovingBox => {
+                let box_x = (sequence as usize * 4) % width.saturating_sub(BOX_SIZE).max(1);
+                let box_y = (sequence as usize * 2) % height.saturating_sub(BOX_SIZE).max(1);
+                let inside = (box_x..box_x + BOX_SIZE).contains(&x)
+                    && (box_y..box_y + BOX_SIZE).contains(&y);
+                if inside { 255 } else { 0 }
+            }
+            // Dark left eye, bright right eye, each with a white marker in
+            // its top left corner to tell flips apart
+            TestPattern::EyeMarkers => {
+                let half = width / 2;
+                let eye_x = if x < half { x } else { x - half };
+                if eye_x < MARKER_SIZE && y < MARKER_SIZE {
+                    255
+                } else if x < half {
+                    64
+                } else {
+                    192
+                }
+            }
+            TestPattern::Noise => {
+                let seed = (sequence as u64) << 32 | (y * width + x) as u64;
+                (seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 56) as u8
+            }
+        }
+    };
+
+    let mut data = vec![128; width * height * 2];
+    for (y, row) in data.chunks_exact_mut(width * 2).enumerate() {
+        for x in 0..width {
+            row[x * 2] = luma(x, y);
+        }
+    }
+    data
+}
+
+// Generator thread, stopped when dropped
+pub struct MockStream {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockStream {
+    // Calls `on_frame` with every generated frame and its sequence number at
+    // `fps` frames per second
+    pub fn start<F>(pattern: TestPattern, size: (usize, usize), fps: u32, mut on_frame: F) -> MockStream
+    where
+        F: FnMut(Vec<u8>, u32) + Send + 'static,
+    {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let running = running.clone();
+            std::thread::spawn(move || {
+                let interval = Duration::from_secs(1) / fps.max(1);
+                let mut sequence = 0;
+                while running.load(Ordering::Relaxed) {
+                    on_frame(generate(pattern, size, sequence), sequence);
+                    sequence = sequence.wrapping_add(1);
+                    std::thread::sleep(interval);
+                }
+            })
+        };
+
+        MockStream {
+            running,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for MockStream {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
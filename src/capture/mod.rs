@@ -0,0 +1,683 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// Frame capture from the Bigscreen Beyond 2e eye tracking camera without a
+// GStreamer pipeline, e.g. for calibration tools. `bigeyesrc` is built on
+// top of this, so both behave the same:
+//
+//   let camera = Camera::open(&Selector::default())?;
+//   for frame in camera.frames() {
+//       println!("frame {}: {} bytes", frame.sequence(), frame.bytes().len());
+//   }
+
+#[cfg(target_os = "linux")]
+pub mod access;
+mod device;
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(target_os = "linux")]
+pub(crate) mod usb;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "mock")]
+use gst::glib;
+
+// USB IDs of the camera
+pub const VENDOR_ID: u16 = 0x35bd;
+pub const PRODUCT_ID: u16 = 0x0202;
+
+// Native mode of the camera
+pub const WIDTH: u32 = 800;
+pub const HEIGHT: u32 = 400;
+pub const FPS: u32 = 90;
+
+// Time without a frame after which `Camera::frames()` ends
+const FRAME_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Pixel format to request from the camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    // Motion JPEG
+    Mjpeg,
+    // Uncompressed YUY2
+    Yuy2,
+}
+
+// Synthetic image produced by the mock backend
+#[cfg(feature = "mock")]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstBigEyeSrcTestPattern")]
+pub enum TestPattern {
+    #[enum_value(name = "Solid: Uniform mid gray", nick = "solid")]
+    Solid = 0,
+    #[enum_value(name = "Gradient: Horizontal black to white ramp", nick = "gradient")]
+    Gradient = 1,
+    #[enum_value(name = "Moving box: White box moving over black", nick = "moving-box")]
+    MovingBox = 2,
+    #[enum_value(
+        name = "Eye markers: Dark left and bright right eye with a marker in each top left corner",
+        nick = "eye-markers"
+    )]
+    EyeMarkers = 3,
+    #[enum_value(name = "Noise: Random luma, different in every frame", nick = "noise")]
+    Noise = 4,
+}
+
+// Which camera to open and how to stream from it
+#[derive(Debug, Clone)]
+pub struct Selector {
+    // Serial number of the camera to open, the first one found if unset
+    pub serial: Option<String>,
+    // Index of the UVC video streaming interface
+    pub interface: u32,
+    pub format: PixelFormat,
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    // Frames kept until they are read, older ones are dropped beyond that
+    pub queue_size: usize,
+    // CPUs the thread delivering the frames is pinned to, one bit per CPU.
+    // 0 leaves it to the scheduler.
+    pub affinity_mask: u64,
+    // Image generated instead of opening a camera
+    #[cfg(feature = "mock")]
+    pub test_pattern: TestPattern,
+}
+
+impl Default for Selector {
+    fn default() -> Self {
+        Selector {
+            serial: None,
+            interface: 0,
+            format: PixelFormat::Mjpeg,
+            width: WIDTH,
+            height: HEIGHT,
+            fps: FPS,
+            queue_size: 8,
+            affinity_mask: 0,
+            #[cfg(feature = "mock")]
+            test_pattern: TestPattern::EyeMarkers,
+        }
+    }
+}
+
+// Stream mode the camera actually agreed to, which can differ from the
+// selected one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mode {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub fourcc: [u8; 4],
+}
+
+// What a camera reports about itself, see `probe()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Probe {
+    pub serial: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    // Supported UVC version, binary coded decimal
+    pub uvc_version: u16,
+    // USB bus number and device address
+    pub usb_address: (u8, u8),
+    pub usb_speed: Option<uvc::UsbSpeed>,
+    // Every combination of format, size and frame rate the camera lists
+    pub modes: Vec<Mode>,
+    // Standard UVC controls that could be read
+    pub controls: Vec<&'static str>,
+}
+
+// Pins the calling thread to the CPUs set in `mask`, one bit per CPU
+#[cfg(target_os = "linux")]
+pub fn pin_thread(mask: u64) -> std::io::Result<()> {
+    // SAFETY: cpu_set_t is plain data, and pid 0 only changes the calling
+    // thread
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for cpu in (0..64).filter(|cpu| mask & (1 << cpu) != 0) {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_thread(_mask: u64) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+// Opens the camera with the given serial number, or the first one, and
+// reads its descriptors without streaming. A camera that is already
+// streaming can't be opened again, use `Camera::probe()` on it instead.
+pub fn probe(serial: Option<&str>) -> Result<Probe, Error> {
+    device::probe(serial)
+}
+
+// A frame as delivered by the camera
+#[derive(Debug, Clone)]
+pub struct Frame {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    sequence: u32,
+    capture_time: Duration,
+}
+
+impl Frame {
+    // Payload, a JPEG image or tightly packed YUY2
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // Frame sequence number as reported by libuvc
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    // Host monotonic time at which the frame finished arriving
+    pub fn capture_time(&self) -> Duration {
+        self.capture_time
+    }
+}
+
+// Reasons opening a camera can fail
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Error {
+    Context(uvc::Error),
+    NotFound(uvc::Error),
+    Open(uvc::Error),
+    Interfaces(uvc::Error),
+    // The requested streaming interface index is out of range
+    NoInterface { index: u32, available: usize },
+    Format(uvc::Error),
+    Stream(uvc::Error),
+    // The mock backend only generates YUY2
+    #[cfg(feature = "mock")]
+    MockFormat,
+}
+
+impl Error {
+    // The step of opening and streaming that failed: context, find, open
+    // or stream
+    pub fn stage(&self) -> &'static str {
+        match self {
+            Error::Context(_) => "context",
+            Error::NotFound(_) => "find",
+            Error::Open(_) | Error::Interfaces(_) | Error::NoInterface { .. } => "open",
+            Error::Format(_) | Error::Stream(_) => "stream",
+            #[cfg(feature = "mock")]
+            Error::MockFormat => "stream",
+        }
+    }
+
+    // The underlying libuvc error, if any
+    pub fn uvc_error(&self) -> Option<uvc::Error> {
+        match *self {
+            Error::Context(e)
+            | Error::NotFound(e)
+            | Error::Open(e)
+            | Error::Interfaces(e)
+            | Error::Format(e)
+            | Error::Stream(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Context(e) => write!(f, "Could not create context: {} ({:?})", e, e),
+            Error::NotFound(e) => write!(f, "Could not find device: {} ({:?})", e, e),
+            Error::Open(e) => write!(f, "Could not open device: {} ({:?})", e, e),
+            Error::Interfaces(e) => {
+                write!(f, "Could not read streaming interfaces: {} ({:?})", e, e)
+            }
+            Error::NoInterface { index, available } => write!(
+                f,
+                "Streaming interface {} not available, device has {}",
+                index, available
+            ),
+            Error::Format(e) => write!(f, "Could not open stream with format: {} ({:?})", e, e),
+            Error::Stream(e) => write!(f, "Could not start stream: {} ({:?})", e, e),
+            #[cfg(feature = "mock")]
+            Error::MockFormat => write!(f, "The mock backend only produces YUY2"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Frames handed over by the stream callback, oldest first
+struct Queue {
+    frames: VecDeque<Frame>,
+    dropped: u64,
+    capacity: usize,
+    // Most frames queued at once
+    high_water: usize,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    ready: Condvar,
+}
+
+impl Shared {
+    // Appends a frame, dropping the oldest one when the queue is full
+    fn push(&self, frame: Frame) {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.frames.len() >= queue.capacity {
+            queue.frames.pop_front();
+            queue.dropped += 1;
+        }
+        queue.frames.push_back(frame);
+        queue.high_water = queue.high_water.max(queue.frames.len());
+        self.ready.notify_all();
+    }
+}
+
+// Handle to the frames queued by a camera. It can outlive the camera and
+// be passed to `Camera::open_with_queue()`, so a consumer keeps reading
+// from the same queue while the camera is reopened.
+#[derive(Clone)]
+pub struct FrameQueue {
+    shared: Arc<Shared>,
+}
+
+impl FrameQueue {
+    // A queue holding up to `capacity` frames, older ones are dropped
+    pub fn new(capacity: usize) -> FrameQueue {
+        FrameQueue {
+            shared: Arc::new(Shared {
+                queue: Mutex::new(Queue {
+                    frames: VecDeque::with_capacity(capacity),
+                    dropped: 0,
+                    capacity: capacity.max(1),
+                    high_water: 0,
+                }),
+                ready: Condvar::new(),
+            }),
+        }
+    }
+
+    // Oldest queued frame, waiting up to `timeout` for one to arrive
+    pub fn try_next(&self, timeout: Duration) -> Option<Frame> {
+        let queue = self.shared.queue.lock().unwrap();
+        let (mut queue, _) = self
+            .shared
+            .ready
+            .wait_timeout_while(queue, timeout, |queue| queue.frames.is_empty())
+            .unwrap();
+        queue.frames.pop_front()
+    }
+
+    // Waits up to `timeout` until a frame is queued, without taking it.
+    // Returns whether there is one.
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let queue = self.shared.queue.lock().unwrap();
+        let (queue, _) = self
+            .shared
+            .ready
+            .wait_timeout_while(queue, timeout, |queue| queue.frames.is_empty())
+            .unwrap();
+        !queue.frames.is_empty()
+    }
+
+    // All queued frames, oldest first, without waiting
+    pub fn drain(&self) -> Vec<Frame> {
+        self.shared.queue.lock().unwrap().frames.drain(..).collect()
+    }
+
+    // Frames dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.shared.queue.lock().unwrap().dropped
+    }
+
+    // Number of queued frames
+    pub fn len(&self) -> usize {
+        self.shared.queue.lock().unwrap().frames.len()
+    }
+
+    // Most frames that were queued at once
+    pub fn high_water_mark(&self) -> usize {
+        self.shared.queue.lock().unwrap().high_water
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Changes how many frames are kept, dropping the oldest ones if more
+    // are queued already
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.capacity = capacity.max(1);
+        while queue.frames.len() > queue.capacity {
+            queue.frames.pop_front();
+            queue.dropped += 1;
+        }
+    }
+
+    // Discards the queued frames, the dropped count and the high-water mark
+    pub fn clear(&self) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.frames.clear();
+        queue.dropped = 0;
+        queue.high_water = 0;
+    }
+}
+
+// An open, streaming camera. Streaming stops when it is dropped.
+pub struct Camera {
+    queue: FrameQueue,
+    device: device::DeviceStack,
+    sizes: Vec<(u32, u32)>,
+    serial: Option<String>,
+    mode: Option<Mode>,
+    probe: Option<Probe>,
+}
+
+impl Camera {
+    // Opens the camera and starts streaming in the selected mode
+    pub fn open(selector: &Selector) -> Result<Camera, Error> {
+        Camera::open_with_queue(selector, &FrameQueue::new(selector.queue_size))
+    }
+
+    // Like `open()`, but queues the frames into `queue`, whose capacity
+    // replaces `selector.queue_size`. Frames left over from a previous
+    // camera are discarded, the dropped count is kept.
+    pub fn open_with_queue(selector: &Selector, queue: &FrameQueue) -> Result<Camera, Error> {
+        queue.shared.queue.lock().unwrap().frames.clear();
+
+        let opened = device::open(selector, &queue.shared)?;
+        let probe = device::describe(&opened.device);
+        Ok(Camera {
+            queue: queue.clone(),
+            device: opened.device,
+            sizes: opened.sizes,
+            serial: opened.serial,
+            mode: opened.mode,
+            probe,
+        })
+    }
+
+    // Serial number of the camera, if it reports one
+    pub fn serial(&self) -> Option<&str> {
+        self.serial.as_deref()
+    }
+
+    // Frame sizes the camera lists for the selected format
+    pub fn sizes(&self) -> &[(u32, u32)] {
+        &self.sizes
+    }
+
+    // Mode negotiated with the camera, if it could be determined
+    pub fn mode(&self) -> Option<Mode> {
+        self.mode
+    }
+
+    // USB bus number and device address of the camera
+    pub fn usb_address(&self) -> Option<(u8, u8)> {
+        self.device
+            .device()
+            .map(|dev| (dev.bus_number(), dev.device_address()))
+    }
+
+    // Speed the camera is connected with, None for the mock backend
+    pub fn usb_speed(&self) -> Option<uvc::UsbSpeed> {
+        self.device.handle().map(uvc::DeviceHandle::usb_speed)
+    }
+
+    // What the camera reports about itself, read through the open handle
+    // when it was opened, so asking doesn't talk to the device
+    pub fn probe(&self) -> Option<Probe> {
+        self.probe.clone()
+    }
+
+    // The queue the camera delivers its frames into
+    pub fn queue(&self) -> &FrameQueue {
+        &self.queue
+    }
+
+    // Oldest queued frame, waiting up to `timeout` for one to arrive
+    pub fn try_next(&self, timeout: Duration) -> Option<Frame> {
+        self.queue.try_next(timeout)
+    }
+
+    // All queued frames, oldest first, without waiting
+    pub fn drain(&self) -> Vec<Frame> {
+        self.queue.drain()
+    }
+
+    // Frames dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped()
+    }
+
+    // Blocking iterator over the frames, ends when none arrived for 5 seconds
+    pub fn frames(&self) -> Frames<'_> {
+        Frames { camera: self }
+    }
+
+    // Current value of a control of a vendor extension unit
+    pub fn extension_control(&self, unit: u8, selector: u8) -> Result<Vec<u8>, uvc::Error> {
+        match self.device.handle() {
+            Some(devh) => devh.extension_control(unit, selector),
+            None => Err(uvc::Error::NotSupported),
+        }
+    }
+
+    pub fn set_extension_control(&self, unit: u8, selector: u8, data: &[u8]) -> Result<(), uvc::Error> {
+        match self.device.handle() {
+            Some(devh) => devh.set_extension_control(unit, selector, data),
+            None => Err(uvc::Error::NotSupported),
+        }
+    }
+
+    // IDs of the units with an exposure time control, and of those with a
+    // gain control. Cameras controlling both sensors together have one each.
+    pub fn exposure_units(&self) -> Result<Vec<u8>, uvc::Error> {
+        match self.device.handle() {
+            Some(devh) => Ok(devh.exposure_terminals()),
+            None => Err(uvc::Error::NotSupported),
+        }
+    }
+
+    pub fn gain_units(&self) -> Result<Vec<u8>, uvc::Error> {
+        match self.device.handle() {
+            Some(devh) => Ok(devh.gain_units()),
+            None => Err(uvc::Error::NotSupported),
+        }
+    }
+
+    // Sets a manual exposure time, in units of 100 microseconds
+    pub fn set_exposure(&self, unit: u8, time: u32) -> Result<(), uvc::Error> {
+        match self.device.handle() {
+            Some(devh) => devh.set_exposure_abs_on(unit, time),
+            None => Err(uvc::Error::NotSupported),
+        }
+    }
+
+    pub fn set_gain(&self, unit: u8, gain: u16) -> Result<(), uvc::Error> {
+        match self.device.handle() {
+            Some(devh) => devh.set_gain_on(unit, gain),
+            None => Err(uvc::Error::NotSupported),
+        }
+    }
+
+    // Auto-exposure mode the camera is in
+    pub fn ae_mode(&self) -> Result<uvc::AutoExposureMode, uvc::Error> {
+        match self.device.handle() {
+            Some(devh) => devh.ae_mode(),
+            None => Err(uvc::Error::NotSupported),
+        }
+    }
+
+    // Whether auto-exposure may lower the frame rate to expose longer
+    pub fn set_ae_priority(&self, priority: uvc::AutoExposurePriority) -> Result<(), uvc::Error> {
+        match self.device.handle() {
+            Some(devh) => devh.set_ae_priority(priority),
+            None => Err(uvc::Error::NotSupported),
+        }
+    }
+
+    // Stops streaming and starts again without closing the device, e.g. to
+    // get a stalled stream going. Nothing is streamed if it fails.
+    pub fn restart_stream(&mut self) -> Result<(), Error> {
+        self.device.restart_stream(&self.queue.shared)
+    }
+
+    // Stops streaming and resets the USB port of the camera, which has to be
+    // opened again afterwards. Returns `uvc::Error::NotFound` when the
+    // camera re-enumerates, possibly with a new address.
+    pub fn reset_port(mut self) -> Result<(), uvc::Error> {
+        self.device.stop_stream();
+        match self.device.handle() {
+            Some(devh) => devh.reset_port(),
+            None => Ok(()),
+        }
+    }
+}
+
+// Iterator returned by `Camera::frames()`
+pub struct Frames<'a> {
+    camera: &'a Camera,
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        self.camera.try_next(FRAME_TIMEOUT)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    fn selector() -> Selector {
+        Selector {
+            format: PixelFormat::Yuy2,
+            ..Selector::default()
+        }
+    }
+
+    fn frame(sequence: u32) -> Frame {
+        Frame {
+            data: vec![0; 4],
+            width: 2,
+            height: 1,
+            sequence,
+            capture_time: Duration::ZERO,
+        }
+    }
+
+    fn sequences(frames: &[Frame]) -> Vec<u32> {
+        frames.iter().map(Frame::sequence).collect()
+    }
+
+    #[test]
+    fn open_streams_the_selected_mode() {
+        gst::init().unwrap();
+        let camera = Camera::open(&selector()).unwrap();
+        assert_eq!(camera.sizes(), [(WIDTH, HEIGHT), (WIDTH / 2, HEIGHT / 2)]);
+        assert_eq!(
+            camera.mode(),
+            Some(Mode {
+                width: WIDTH,
+                height: HEIGHT,
+                fps: FPS,
+                fourcc: *b"YUY2",
+            })
+        );
+        assert_eq!(camera.usb_speed(), None);
+
+        let frames = camera.frames().take(3).collect::<Vec<_>>();
+        assert_eq!(frames.len(), 3);
+        for frame in &frames {
+            assert_eq!((frame.width(), frame.height()), (WIDTH, HEIGHT));
+            assert_eq!(frame.bytes().len(), (WIDTH * HEIGHT * 2) as usize);
+        }
+        assert!(frames.windows(2).all(|pair| pair[0].sequence() < pair[1].sequence()));
+    }
+
+    #[test]
+    fn mock_only_offers_yuy2() {
+        gst::init().unwrap();
+        let selector = Selector {
+            format: PixelFormat::Mjpeg,
+            ..selector()
+        };
+        assert!(matches!(Camera::open(&selector), Err(Error::MockFormat)));
+    }
+
+    #[test]
+    fn try_next_times_out_without_frames() {
+        gst::init().unwrap();
+        let mut camera = Camera::open(&selector()).unwrap();
+        camera.device.stop_stream();
+        camera.drain();
+
+        let started = Instant::now();
+        assert!(camera.try_next(Duration::from_millis(50)).is_none());
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_millis(50), "returned after {:?}", waited);
+        assert!(waited < Duration::from_secs(1), "returned after {:?}", waited);
+    }
+
+    #[test]
+    fn full_queue_drops_the_oldest() {
+        let queue = FrameQueue::new(3);
+        for sequence in 0..5 {
+            queue.shared.push(frame(sequence));
+        }
+        assert_eq!(queue.dropped(), 2);
+        assert_eq!(queue.high_water_mark(), 3);
+        assert_eq!(sequences(&queue.drain()), [2, 3, 4]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn set_capacity_keeps_the_newest() {
+        let queue = FrameQueue::new(8);
+        for sequence in 0..4 {
+            queue.shared.push(frame(sequence));
+        }
+        queue.set_capacity(2);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.dropped(), 2);
+        // The mark is what was queued at most, not what fits now
+        assert_eq!(queue.high_water_mark(), 4);
+
+        queue.shared.push(frame(4));
+        assert_eq!(queue.dropped(), 3);
+        assert_eq!(sequences(&queue.drain()), [3, 4]);
+
+        queue.clear();
+        assert_eq!((queue.dropped(), queue.high_water_mark()), (0, 0));
+    }
+}
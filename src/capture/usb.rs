@@ -0,0 +1,28 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// The USB devices the kernel lists in sysfs
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const USB_DEVICES: &str = "/sys/bus/usb/devices";
+
+// Sysfs directories of the attached USB devices and their interfaces
+pub fn devices() -> impl Iterator<Item = PathBuf> {
+    fs::read_dir(USB_DEVICES)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+}
+
+// Value of one of the device's attributes, without the trailing newline
+pub fn attribute(device: &Path, name: &str) -> Option<String> {
+    Some(fs::read_to_string(device.join(name)).ok()?.trim().to_string())
+}
+
+// Bus number and address of the device
+pub fn address(device: &Path) -> Option<(u8, u8)> {
+    Some((attribute(device, "busnum")?.parse().ok()?, attribute(device, "devnum")?.parse().ok()?))
+}
@@ -1,6 +1,10 @@
 use gst::glib;
 
 mod bigeyesrc;
+pub mod capture;
+pub mod meta;
+
+pub use bigeyesrc::{FrameTiming, clear_frame_hook, set_frame_hook};
 
 fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     bigeyesrc::register(plugin)?;
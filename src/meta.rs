@@ -0,0 +1,187 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// Per-frame metadata attached by `bigeyesrc`.
+//
+// Every buffer carries a `BigEyeFrameMeta` custom meta. Buffer metas do not
+// survive `gdppay`/`shmsink`, so with `serialize-meta=true` the element also
+// pushes a serialized custom downstream event carrying the same structure
+// right before each buffer. The first buffer after start goes without one,
+// as the segment that BaseSrc pushes along with it has to come first. On
+// the receiving side `restore_from_events()` turns those events back into
+// metas on the buffers that follow them:
+//
+//   shmsrc ! gdpdepay ! <pad with restore_from_events()> ! ...
+//
+// MJPEG caps don't survive `jpegdec`, so the stereo layout of MJPEG output
+// is announced with the `bigeye-multiview-mode` tag instead of caps fields.
+
+use std::sync::{Arc, Mutex};
+
+use gst::glib;
+use gst::prelude::*;
+
+// Name of the custom meta and of the structure it holds
+pub const FRAME_META_NAME: &str = "BigEyeFrameMeta";
+
+// Name of the custom downstream event used to carry the meta across GDP
+pub const FRAME_META_EVENT_NAME: &str = "bigeye-frame-meta";
+
+// Custom tag holding the `multiview-mode` caps string of the stream, e.g.
+// "side-by-side" with the left eye first
+pub enum MultiviewModeTag {}
+
+impl<'a> gst::tags::Tag<'a> for MultiviewModeTag {
+    type TagType = &'a str;
+    const TAG_NAME: &'static glib::GStr = glib::gstr!("bigeye-multiview-mode");
+}
+
+impl gst::tags::CustomTag<'_> for MultiviewModeTag {
+    const FLAG: gst::TagFlag = gst::TagFlag::Meta;
+    const NICK: &'static glib::GStr = glib::gstr!("multiview mode");
+    const DESCRIPTION: &'static glib::GStr =
+        glib::gstr!("Stereo layout of the frames, as a multiview-mode caps string");
+}
+
+// Decoded contents of a `BigEyeFrameMeta`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameMeta {
+    // Frame sequence number as reported by libuvc
+    pub sequence: u32,
+    // Host monotonic time at which the frame finished arriving
+    pub capture_time: gst::ClockTime,
+    // Set when the buffer repeats the last good frame instead of a new one
+    pub repeat: bool,
+    // Mean luma (0-255) of the left and right eye, when the brightness
+    // monitor sampled this frame
+    pub brightness: Option<(f64, f64)>,
+    // `checksum()` of the buffer contents as pushed, with checksum=true
+    pub checksum: Option<u64>,
+}
+
+// 64 bit FNV-1a hash of every byte of a frame, as used for the checksum
+// field. Cheap enough to run on every frame at full rate.
+pub fn checksum(data: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    data.iter()
+        .fold(OFFSET_BASIS, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
+
+// Whether the contents of a buffer still match the checksum in its
+// `BigEyeFrameMeta`. None if it has no checksum or can't be mapped.
+pub fn verify_checksum(buffer: &gst::BufferRef) -> Option<bool> {
+    let expected = FrameMeta::from_buffer(buffer)?.checksum?;
+    let map = buffer.map_readable().ok()?;
+    Some(checksum(&map) == expected)
+}
+
+// Registers the custom meta and tag. Safe to call more than once, and needed
+// in the receiving process before `restore_from_events()` can attach metas.
+pub fn register() {
+    if !gst::meta::CustomMeta::is_registered(FRAME_META_NAME) {
+        gst::meta::CustomMeta::register(FRAME_META_NAME, &[]);
+    }
+    if !gst::tags::tag_exists(<MultiviewModeTag as gst::tags::Tag>::TAG_NAME) {
+        gst::tags::register::<MultiviewModeTag>();
+    }
+}
+
+impl FrameMeta {
+    fn from_structure(s: &gst::StructureRef) -> Option<FrameMeta> {
+        Some(FrameMeta {
+            sequence: s.get("sequence").ok()?,
+            capture_time: s.get("capture-time").ok()?,
+            repeat: s.get("repeat").ok()?,
+            brightness: s
+                .get("brightness-left")
+                .ok()
+                .zip(s.get("brightness-right").ok()),
+            checksum: s.get("checksum").ok(),
+        })
+    }
+
+    fn write_structure(&self, s: &mut gst::StructureRef) {
+        s.set("sequence", self.sequence);
+        s.set("capture-time", self.capture_time);
+        s.set("repeat", self.repeat);
+        match self.brightness {
+            Some((left, right)) => {
+                s.set("brightness-left", left);
+                s.set("brightness-right", right);
+            }
+            None => {
+                s.remove_fields(["brightness-left", "brightness-right"]);
+            }
+        }
+        match self.checksum {
+            Some(checksum) => s.set("checksum", checksum),
+            None => s.remove_field("checksum"),
+        }
+    }
+
+    // Attaches the metadata to a buffer, replacing any existing values
+    pub fn add(&self, buffer: &mut gst::BufferRef) {
+        let mut meta = match gst::meta::CustomMeta::from_mut_buffer(buffer, FRAME_META_NAME) {
+            Ok(meta) => meta,
+            Err(_) => gst::meta::CustomMeta::add(buffer, FRAME_META_NAME)
+                .expect("BigEyeFrameMeta not registered"),
+        };
+        self.write_structure(meta.mut_structure());
+    }
+
+    // Reads the metadata from a buffer, if present
+    pub fn from_buffer(buffer: &gst::BufferRef) -> Option<FrameMeta> {
+        let meta = gst::meta::CustomMeta::from_buffer(buffer, FRAME_META_NAME).ok()?;
+        FrameMeta::from_structure(meta.structure())
+    }
+
+    // Builds the serialized event that carries this metadata through GDP
+    pub fn to_event(&self) -> gst::Event {
+        let mut s = gst::Structure::new_empty(FRAME_META_EVENT_NAME);
+        self.write_structure(&mut s);
+        gst::event::CustomDownstream::new(s)
+    }
+
+    // Reads the metadata from an event created by `to_event()`
+    pub fn from_event(event: &gst::EventRef) -> Option<FrameMeta> {
+        match event.view() {
+            gst::EventView::CustomDownstream(ev) => {
+                let s = ev.structure()?;
+                if s.name() != FRAME_META_EVENT_NAME {
+                    return None;
+                }
+                FrameMeta::from_structure(s)
+            }
+            _ => None,
+        }
+    }
+}
+
+// Installs a probe on `pad` that consumes the `bigeye-frame-meta` events and
+// attaches their contents as a `BigEyeFrameMeta` to the next buffer.
+pub fn restore_from_events(pad: &gst::Pad) -> Option<gst::PadProbeId> {
+    register();
+
+    let pending: Arc<Mutex<Option<FrameMeta>>> = Arc::new(Mutex::new(None));
+    pad.add_probe(
+        gst::PadProbeType::BUFFER | gst::PadProbeType::EVENT_DOWNSTREAM,
+        move |_pad, info| match info.data {
+            Some(gst::PadProbeData::Event(ref event)) => match FrameMeta::from_event(event) {
+                Some(meta) => {
+                    *pending.lock().unwrap() = Some(meta);
+                    gst::PadProbeReturn::Drop
+                }
+                None => gst::PadProbeReturn::Ok,
+            },
+            Some(gst::PadProbeData::Buffer(ref mut buffer)) => {
+                if let Some(meta) = pending.lock().unwrap().take() {
+                    meta.add(buffer.make_mut());
+                }
+                gst::PadProbeReturn::Ok
+            }
+            _ => gst::PadProbeReturn::Ok,
+        },
+    )
+}
@@ -0,0 +1,581 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// Tests of the element against the mock backend, run with
+//
+//   cargo test --features mock
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+
+use gst::prelude::*;
+use gst_base::prelude::*;
+use gst_check::Harness;
+use gstbigeye::meta::{self, FrameMeta};
+
+fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstbigeye::plugin_register_static().expect("register plugin");
+    });
+}
+
+// Harness around a playing bigeyesrc producing raw frames, with the given
+// properties set before it started
+fn harness(properties: &[(&str, &str)]) -> Harness {
+    init();
+    let mut h = Harness::new("bigeyesrc");
+    let src = h.element().unwrap();
+    src.set_property_from_str("format", "raw");
+    for (name, value) in properties {
+        src.set_property_from_str(name, value);
+    }
+    h.play();
+    h
+}
+
+// Luma of the first pixel of a raw buffer
+fn first_luma(buffer: &gst::Buffer) -> u8 {
+    buffer.map_readable().unwrap()[0]
+}
+
+// Starts the pipeline and returns it with its bigeyesrc, named src
+fn play(description: &str) -> (gst::Pipeline, gst::Element) {
+    init();
+    let pipeline = gst::parse::launch(description)
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+    let src = pipeline.by_name("src").unwrap();
+    pipeline.set_state(gst::State::Playing).unwrap();
+    (pipeline, src)
+}
+
+// Debug strings of the warnings with the given text, until `count` of them
+// were posted or `timeout` passed
+fn warnings(pipeline: &gst::Pipeline, text: &str, count: usize, timeout: Duration) -> Vec<String> {
+    let bus = pipeline.bus().unwrap();
+    let deadline = Instant::now() + timeout;
+    let mut found = Vec::new();
+    while found.len() < count {
+        let Some(left) = deadline.checked_duration_since(Instant::now()) else {
+            break;
+        };
+        let left = gst::ClockTime::from_nseconds(left.as_nanos() as u64);
+        let Some(message) = bus.timed_pop_filtered(left, &[gst::MessageType::Warning, gst::MessageType::Error])
+        else {
+            break;
+        };
+        match message.view() {
+            gst::MessageView::Warning(warning) if warning.error().to_string() == text => {
+                found.push(warning.debug().map(|debug| debug.to_string()).unwrap_or_default());
+            }
+            gst::MessageView::Error(error) => panic!("error: {} ({:?})", error.error(), error.debug()),
+            _ => {}
+        }
+    }
+    found
+}
+
+fn stat(src: &gst::Element, field: &str) -> u64 {
+    src.property::<gst::Structure>("stats").get(field).unwrap()
+}
+
+const FROZEN: &str = "The camera keeps sending the same frame";
+
+#[test]
+fn freeze_warned_after_threshold() {
+    let (pipeline, src) = play(
+        "bigeyesrc name=src format=raw test-pattern=solid freeze-detection=true freeze-threshold=10 ! fakesink",
+    );
+
+    // The first frame and 11 repeats of it are more than 10 identical ones
+    let found = warnings(&pipeline, FROZEN, 1, Duration::from_secs(5));
+    assert_eq!(found.len(), 1, "no freeze warning");
+    assert!(found[0].contains("12 identical frames in a row"), "{}", found[0]);
+
+    // With freeze-action=warn a freeze is only reported once
+    let again = warnings(&pipeline, FROZEN, 1, Duration::from_secs(1));
+    assert!(again.is_empty(), "freeze reported again: {:?}", again);
+    assert_eq!(stat(&src, "freezes"), 1);
+    pipeline.set_state(gst::State::Null).unwrap();
+}
+
+#[test]
+fn freeze_reset_starts_detection_over() {
+    let (pipeline, src) = play(
+        "bigeyesrc name=src format=raw test-pattern=solid freeze-detection=true freeze-threshold=10 \
+         freeze-action=reset ! fakesink",
+    );
+
+    // The reopened mock still repeats its frame, so it is reported again
+    let found = warnings(&pipeline, FROZEN, 2, Duration::from_secs(5));
+    assert_eq!(found.len(), 2, "freeze-action=reset did not reopen the camera");
+    assert!(found.iter().all(|debug| debug.contains("12 identical frames in a row")), "{:?}", found);
+    assert!(stat(&src, "freezes") >= 2);
+    pipeline.set_state(gst::State::Null).unwrap();
+}
+
+#[test]
+fn changing_frames_not_frozen() {
+    let (pipeline, src) = play(
+        "bigeyesrc name=src format=raw test-pattern=noise freeze-detection=true freeze-threshold=1 ! fakesink",
+    );
+
+    let found = warnings(&pipeline, FROZEN, 1, Duration::from_secs(1));
+    assert!(found.is_empty(), "changing frames reported frozen: {:?}", found);
+    assert!(stat(&src, "delivered") > 10);
+    assert_eq!(stat(&src, "frozen"), 0);
+    assert_eq!(stat(&src, "freezes"), 0);
+    pipeline.set_state(gst::State::Null).unwrap();
+}
+
+#[test]
+fn seek_refused() {
+    let mut h = harness(&[]);
+    h.pull().expect("no buffer");
+    let src = h.element().unwrap();
+
+    let seek = gst::event::Seek::new(
+        1.0,
+        gst::SeekFlags::FLUSH,
+        gst::SeekType::Set,
+        gst::ClockTime::SECOND,
+        gst::SeekType::None,
+        gst::ClockTime::NONE,
+    );
+    assert!(!h.push_upstream_event(seek), "seek accepted");
+
+    let mut query = gst::query::Seeking::new(gst::Format::Time);
+    assert!(src.static_pad("src").unwrap().query(&mut query));
+    assert!(!query.result().0, "reported as seekable");
+
+    // The refused seek leaves the stream running
+    h.pull().expect("no buffer after the seek");
+}
+
+#[test]
+fn live_property_applies_immediately() {
+    // Set before starting, it applies from the first buffer
+    let mut h = harness(&[("test-pattern", "solid"), ("invert", "true")]);
+    assert_eq!(first_luma(&h.pull().unwrap()), 255 - 128);
+
+    // Set while playing, it applies without a restart. Buffers produced
+    // before the change may still be queued.
+    let mut h = harness(&[("test-pattern", "solid")]);
+    assert_eq!(first_luma(&h.pull().unwrap()), 128);
+    h.element().unwrap().set_property("invert", true);
+    let inverted = (0..20).any(|_| first_luma(&h.pull().unwrap()) == 255 - 128);
+    assert!(inverted, "invert set while playing did not apply");
+}
+
+// Whether a start-time property is in effect, told from the next buffer
+type InEffect = fn(&mut Harness) -> bool;
+
+// Current caps of the harnessed element
+fn current_caps(h: &Harness) -> gst::Caps {
+    h.element().unwrap().static_pad("src").unwrap().current_caps().unwrap()
+}
+
+// A property of each group in START_TIME_PROPERTIES whose effect shows in
+// the mock's output, with a value other than the default. pts-base,
+// low-latency and the options for opening the camera don't change what
+// its buffers look like.
+const START_TIME_CASES: &[(&str, &str, InEffect)] = &[
+    // Output format and size
+    ("mono", "true", |h| h.pull().unwrap().size() == 800 * 400),
+    ("anaglyph", "true", |h| {
+        h.pull().unwrap();
+        let caps = current_caps(h);
+        let s = caps.structure(0).unwrap();
+        (s.get::<i32>("width"), s.get::<i32>("height")) == (Ok(400), Ok(400))
+    }),
+    ("output-aspect", "1/1", |h| h.pull().unwrap().size() == 800 * 800 * 2),
+    ("multiview", "true", |h| {
+        h.pull().unwrap();
+        let caps = current_caps(h);
+        caps.structure(0).unwrap().get::<&str>("multiview-mode") == Ok("side-by-side")
+    }),
+    // Metadata
+    ("serialize-meta", "true", |h| {
+        h.pull().unwrap();
+        std::iter::from_fn(|| h.try_pull_event())
+            .any(|event| event.structure().is_some_and(|s| s.name() == meta::FRAME_META_EVENT_NAME))
+    }),
+    // Opening the camera
+    ("test-pattern", "solid", |h| first_luma(&h.pull().unwrap()) == 128),
+];
+
+#[test]
+fn start_time_properties_wait_for_restart() {
+    for &(name, value, in_effect) in START_TIME_CASES {
+        // Set before starting, it applies from the first buffer
+        let mut h = harness(&[(name, value)]);
+        assert!(in_effect(&mut h), "{} set before starting did not apply", name);
+        let expected = h.element().unwrap().property_value(name).serialize().unwrap();
+
+        // Set while playing, it is stored but only applies on the next start
+        let mut h = harness(&[]);
+        h.pull().unwrap();
+        let src = h.element().unwrap();
+        src.set_property_from_str(name, value);
+        assert_eq!(src.property_value(name).serialize().unwrap(), expected, "{} was not stored", name);
+        for _ in 0..10 {
+            assert!(!in_effect(&mut h), "{} applied while playing", name);
+        }
+
+        src.set_state(gst::State::Null).unwrap();
+        h.play();
+        // Buffers from before the restart may still be queued
+        let restarted = (0..20).any(|_| in_effect(&mut h));
+        assert!(restarted, "{} did not apply after a restart", name);
+    }
+}
+
+#[test]
+fn properties_accessed_while_streaming() {
+    let mut h = harness(&[]);
+    let src = h.element().unwrap();
+    let stop = Arc::new(AtomicBool::new(false));
+
+    // Getters and setters of both live and start-time properties, each of
+    // which takes the settings, state or stream lock create() also needs
+    let threads = (0..4)
+        .map(|i| {
+            let (src, stop) = (src.clone(), stop.clone());
+            std::thread::spawn(move || {
+                let mut accesses = 0u64;
+                while !stop.load(Ordering::Relaxed) {
+                    src.set_property("invert", accesses.is_multiple_of(2));
+                    src.set_property("mono", i % 2 == 0);
+                    let _ = src.property::<gst::Structure>("stats");
+                    let _ = src.property::<u32>("queue-level");
+                    let _ = src.property::<bool>("capturing");
+                    accesses += 1;
+                }
+                accesses
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // The mock camera delivers 90 frames per second, contention that held
+    // up create() would show up as missing ones
+    let started = Instant::now();
+    let (mut pushed, mut last_pts) = (0u64, None);
+    while started.elapsed() < Duration::from_secs(1) {
+        let buffer = h.pull().expect("no buffer while properties were accessed");
+        assert!(buffer.pts() > last_pts, "PTS {:?} after {:?}", buffer.pts(), last_pts);
+        last_pts = buffer.pts();
+        pushed += 1;
+    }
+
+    stop.store(true, Ordering::Relaxed);
+    for thread in threads {
+        assert!(thread.join().unwrap() > 0);
+    }
+    assert!(pushed >= 60, "only {} buffers pushed in a second", pushed);
+    assert!(stat(&src, "delivered") >= pushed);
+}
+
+#[test]
+fn properties_accessed_during_self_test() {
+    init();
+    let src = gst::ElementFactory::make("bigeyesrc").property_from_str("format", "raw").build().unwrap();
+    let test = {
+        let src = src.clone();
+        std::thread::spawn(move || src.emit_by_name::<Option<gst::Structure>>("self-test", &[]))
+    };
+
+    // The self-test captures for a second, the state lock is free meanwhile
+    std::thread::sleep(Duration::from_millis(200));
+    let started = Instant::now();
+    let _ = src.property::<gst::Structure>("stats");
+    assert!(started.elapsed() < Duration::from_millis(500), "stats took {:?}", started.elapsed());
+
+    let verdict = test.join().unwrap().expect("the self-test did not run");
+    assert!(verdict.get::<u32>("frames").unwrap() > 0);
+}
+
+#[test]
+fn properties_set_on_the_element_win_over_the_config_file() {
+    init();
+    let path = std::env::temp_dir().join(format!("bigeyesrc-{}.conf", std::process::id()));
+    std::fs::write(&path, "[bigeyesrc]\ninvert = true\nlog-throttle = 250\n").unwrap();
+    let src = gst::ElementFactory::make("bigeyesrc")
+        .property("config-file", path.to_str().unwrap())
+        .property("invert", false)
+        .build()
+        .unwrap();
+
+    // The file is read on the way to READY
+    src.set_state(gst::State::Ready).unwrap();
+    assert!(!src.property::<bool>("invert"), "the file overrode invert set on the element");
+    assert_eq!(src.property::<u32>("log-throttle"), 250);
+    src.set_state(gst::State::Null).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn frame_meta_survives_gdp() {
+    init();
+    let pipeline = gst::parse::launch(
+        "bigeyesrc name=src format=raw test-pattern=noise serialize-meta=true checksum=true ! gdppay ! gdpdepay ! fakesink name=sink",
+    )
+    .unwrap()
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+
+    // Metas as they leave the source, and as restored after the GDP round-trip
+    let record = |pad: &gst::Pad| {
+        let metas = Arc::new(Mutex::new(Vec::new()));
+        let recorded = metas.clone();
+        pad.add_probe(gst::PadProbeType::BUFFER, move |_, info| {
+            if let Some(buffer) = info.buffer() {
+                let checksum_ok = meta::verify_checksum(buffer);
+                recorded.lock().unwrap().push((FrameMeta::from_buffer(buffer), checksum_ok));
+            }
+            gst::PadProbeReturn::Ok
+        });
+        metas
+    };
+    let sent = record(&pipeline.by_name("src").unwrap().static_pad("src").unwrap());
+    let sink_pad = pipeline.by_name("sink").unwrap().static_pad("sink").unwrap();
+    // Probes run in the order they were added, so the metas are restored
+    // before they are recorded
+    meta::restore_from_events(&sink_pad).unwrap();
+    let received = record(&sink_pad);
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while received.lock().unwrap().len() < 20 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    let sent = sent.lock().unwrap();
+    let received = received.lock().unwrap();
+    assert!(received.len() >= 20, "only {} buffers arrived", received.len());
+    // The first buffer goes out before the segment, without an event
+    assert_eq!(received[0].0, None);
+    for (index, (restored, checksum_ok)) in received.iter().enumerate().skip(1) {
+        let restored = restored.unwrap_or_else(|| panic!("buffer {} has no restored meta", index));
+        let original = sent[index].0.unwrap();
+        assert_eq!(restored.sequence, original.sequence);
+        assert_eq!(restored.capture_time, original.capture_time);
+        assert_eq!(restored.checksum, original.checksum);
+        assert!(restored.checksum.is_some());
+        assert_eq!(*checksum_ok, Some(true), "buffer {} doesn't match its checksum", index);
+    }
+}
+
+#[test]
+fn allocation_metas() {
+    init();
+    let mut h = Harness::new("bigeyesrc");
+    h.add_propose_allocation_meta(gst_video::VideoMeta::meta_api(), None);
+    h.add_propose_allocation_meta(gst_video::VideoCropMeta::meta_api(), None);
+    let src = h.element().unwrap();
+    src.set_property_from_str("format", "raw");
+    src.set_property_from_str("output-aspect", "1/1");
+    h.play();
+    let buffer = h.pull().expect("no buffer");
+
+    // The 800x400 frame is padded to a square and the crop meta points at it
+    let video_meta = buffer
+        .meta::<gst_video::VideoMeta>()
+        .map(|meta| (meta.format(), meta.width(), meta.height()));
+    assert_eq!(video_meta, Some((gst_video::VideoFormat::Yuy2, 800, 800)));
+    let crop = buffer.meta::<gst_video::VideoCropMeta>().map(|meta| meta.rect());
+    assert_eq!(crop, Some((0, 200, 800, 400)));
+    assert!(FrameMeta::from_buffer(&buffer).is_some(), "no BigEyeFrameMeta");
+}
+
+#[test]
+fn unfixed_caps_rejected() {
+    let mut h = harness(&[]);
+    h.pull().expect("no buffer");
+    let element = h.element().unwrap();
+    let src = element.downcast_ref::<gst_base::BaseSrc>().unwrap();
+    let pad = src.static_pad("src").unwrap();
+    let current = pad.current_caps();
+
+    let ranged = gst::Caps::builder("video/x-raw")
+        .field("format", "YUY2")
+        .field("width", 800)
+        .field("height", 400)
+        .field(
+            "framerate",
+            gst::FractionRange::new(gst::Fraction::new(30, 1), gst::Fraction::new(90, 1)),
+        )
+        .build();
+    assert!(src.set_caps(&ranged).is_err(), "caps with a framerate range were accepted");
+    h.pull().expect("no buffer after the rejected caps");
+    assert_eq!(pad.current_caps(), current);
+}
+
+#[test]
+fn negotiates_the_second_mock_mode() {
+    let (pipeline, src) = play(
+        "bigeyesrc name=src format=raw strict-resolution=true \
+         ! video/x-raw,width=400,height=200,framerate=60/1 ! appsink name=sink sync=false",
+    );
+    let sink = pipeline.by_name("sink").unwrap();
+    let samples = (0..40)
+        .map(|_| sink.emit_by_name::<Option<gst::Sample>>("pull-sample", &[]).expect("no sample"))
+        .collect::<Vec<_>>();
+    let active = src.property::<Option<gst::Structure>>("active-format").expect("no active format");
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    let caps = samples.last().unwrap().caps_owned().unwrap();
+    let s = caps.structure(0).unwrap();
+    assert_eq!(s.get::<i32>("width"), Ok(400));
+    assert_eq!(s.get::<i32>("height"), Ok(200));
+    assert_eq!(s.get::<gst::Fraction>("framerate"), Ok(gst::Fraction::new(60, 1)));
+
+    // The mock streams the negotiated mode itself, without scaling
+    assert_eq!(active.get::<u32>("width"), Ok(400));
+    assert_eq!(active.get::<u32>("height"), Ok(200));
+    assert_eq!(active.get::<u32>("fps"), Ok(60));
+
+    let buffers = samples.iter().map(|sample| sample.buffer_owned().unwrap()).collect::<Vec<_>>();
+    for buffer in &buffers {
+        assert_eq!(buffer.size(), 400 * 200 * 2);
+        assert_eq!(buffer.duration(), gst::ClockTime::SECOND.mul_div_floor(1, 60));
+    }
+    // Frames arrive at 60 rather than the native 90 fps. The first ones
+    // may still be from before the switch.
+    let recent = &buffers[10..];
+    let span = recent.last().unwrap().pts().unwrap() - recent[0].pts().unwrap();
+    let interval = span / (recent.len() as u64 - 1);
+    assert!(
+        (14..22).contains(&interval.mseconds()),
+        "frames arrived every {} instead of every 16.7 ms",
+        interval
+    );
+}
+
+#[test]
+fn failed_start_posts_one_error_with_details() {
+    init();
+    // The mock camera only streams raw frames
+    let pipeline = gst::parse::launch("bigeyesrc format=mjpeg ! fakesink").unwrap();
+    assert!(pipeline.set_state(gst::State::Paused).is_err());
+
+    let bus = pipeline.bus().unwrap();
+    let errors = std::iter::from_fn(|| bus.pop_filtered(&[gst::MessageType::Error])).collect::<Vec<_>>();
+    pipeline.set_state(gst::State::Null).unwrap();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    let gst::MessageView::Error(error) = errors[0].view() else {
+        unreachable!();
+    };
+    let details = error.details().expect("no details");
+    assert_eq!(details.name(), "bigeyesrc-error");
+    assert!(details.has_field("stage"));
+}
+
+#[test]
+fn tee_branches_share_buffers() {
+    let (pipeline, _src) = play(
+        "bigeyesrc name=src format=raw test-pattern=moving-box ! tee name=t \
+         t. ! queue ! appsink name=recorder sync=false \
+         t. ! queue ! appsink name=analyzer sync=false",
+    );
+    let pull = |name: &str| -> Vec<gst::Buffer> {
+        let sink = pipeline.by_name(name).unwrap();
+        (0..10)
+            .map(|_| {
+                let sample = sink.emit_by_name::<Option<gst::Sample>>("pull-sample", &[]);
+                sample.expect("no sample").buffer_owned().unwrap()
+            })
+            .collect()
+    };
+    let recorded = pull("recorder");
+    let analyzed = pull("analyzer");
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    for (a, b) in recorded.iter().zip(&analyzed) {
+        assert_eq!(a.pts(), b.pts());
+        assert_eq!(*a.map_readable().unwrap(), *b.map_readable().unwrap());
+        // tee hands both branches the same buffer instead of a copy
+        assert_eq!(a.as_ptr(), b.as_ptr(), "buffer was copied for one branch");
+    }
+}
+
+// Starts a pipeline whose "slow" identity holds each buffer for 50 ms, so
+// frames pile up in the element. Returns once a few are queued, with the
+// number of buffers that reached the identity.
+fn play_slow(description: &str) -> (gst::Pipeline, gst::Element, Arc<AtomicUsize>) {
+    let (pipeline, src) = play(description);
+    let pushed = Arc::new(AtomicUsize::new(0));
+    let counter = pushed.clone();
+    let slow = pipeline.by_name("slow").unwrap().static_pad("sink").unwrap();
+    slow.add_probe(gst::PadProbeType::BUFFER, move |_, _| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        gst::PadProbeReturn::Ok
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while pushed.load(Ordering::SeqCst) < 3 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    // Right after a buffer went in, the next ones queue for its 50 ms
+    let count = pushed.load(Ordering::SeqCst);
+    while pushed.load(Ordering::SeqCst) == count && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    std::thread::sleep(Duration::from_millis(40));
+    (pipeline, src, pushed)
+}
+
+#[test]
+fn drain_on_stop_pushes_queued_frames_before_eos() {
+    let (pipeline, src, pushed) =
+        play_slow("bigeyesrc name=src format=raw drain-on-stop=true ! identity name=slow sleep-time=50000 ! fakesink");
+    let before = pushed.load(Ordering::SeqCst);
+    let sent = Instant::now();
+    assert!(src.send_event(gst::event::Eos::new()));
+    assert!(sent.elapsed() < Duration::from_millis(20), "sending the EOS waited for the drain");
+
+    let bus = pipeline.bus().unwrap();
+    let message = bus.timed_pop_filtered(
+        gst::ClockTime::from_seconds(5),
+        &[gst::MessageType::Eos, gst::MessageType::Error],
+    );
+    let drained = pushed.load(Ordering::SeqCst) - before;
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    assert_eq!(message.map(|message| message.type_()), Some(gst::MessageType::Eos));
+    assert!(drained >= 2, "only {} frame(s) were pushed before the EOS", drained);
+}
+
+#[test]
+fn drain_on_stop_pushes_queued_frames_when_stopped() {
+    // The queue keeps taking buffers while the paused sink holds its preroll
+    let (pipeline, _src, pushed) = play_slow(
+        "bigeyesrc name=src format=raw drain-on-stop=true ! identity name=slow sleep-time=50000 ! queue ! fakesink",
+    );
+    let before = pushed.load(Ordering::SeqCst);
+    let stopping = Instant::now();
+    pipeline.set_state(gst::State::Null).unwrap();
+    let drained = pushed.load(Ordering::SeqCst) - before;
+
+    assert!(drained >= 2, "only {} frame(s) were pushed while stopping", drained);
+    assert!(stopping.elapsed() < Duration::from_secs(1), "stopping took {:?}", stopping.elapsed());
+}
+
+#[test]
+fn eye_controls_without_units_warn() {
+    // The mock camera has no exposure or gain controls
+    let (pipeline, src) = play("bigeyesrc name=src format=raw left-exposure=100 ! fakesink");
+    let found = warnings(&pipeline, "The camera has no exposure control", 1, Duration::from_secs(5));
+    assert_eq!(found.len(), 1, "no warning about the missing exposure control");
+
+    // Changing a control while playing tries again, and streaming goes on
+    src.set_property("right-gain", 8i32);
+    let found = warnings(&pipeline, "The camera has no gain control", 1, Duration::from_secs(5));
+    assert_eq!(found.len(), 1, "no warning about the missing gain control");
+    assert!(stat(&src, "delivered") > 0);
+    pipeline.set_state(gst::State::Null).unwrap();
+}
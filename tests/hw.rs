@@ -0,0 +1,189 @@
+// Licensed under the Apache License
+// SPDX-License-Identifier: Apache-2.0
+
+// Tests against a connected headset, run before a release with
+//
+//   cargo test --features hw-tests --test hw -- --test-threads=1 --nocapture
+//
+// Every test prints one `hw-test` line with its results for lab CI to pick
+// up. They all share the one camera, so they must not run in parallel.
+
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+
+use gst::glib;
+use gst::prelude::*;
+use gst_check::Harness;
+
+// Serializes the tests in case --test-threads=1 was forgotten
+static CAMERA: Mutex<()> = Mutex::new(());
+
+fn init() -> std::sync::MutexGuard<'static, ()> {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        gst::init().unwrap();
+        gstbigeye::plugin_register_static().expect("register plugin");
+    });
+    CAMERA.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// Prints the machine readable result line of a test
+fn summary(test: &str, fields: &[(&str, String)]) {
+    let fields = fields
+        .iter()
+        .map(|(name, value)| format!(" {}={}", name, value))
+        .collect::<String>();
+    println!("hw-test test={}{}", test, fields);
+}
+
+fn wait_for_state(element: &impl IsA<gst::Element>, state: gst::State) {
+    let (result, current, _) = element.state(gst::ClockTime::from_seconds(10));
+    result.expect("state change failed");
+    assert_eq!(current, state);
+}
+
+#[test]
+fn start_stop_cycles() {
+    let _camera = init();
+    let pipeline = gst::parse::launch("bigeyesrc ! fakesink").unwrap();
+
+    let started = Instant::now();
+    for _ in 0..20 {
+        pipeline.set_state(gst::State::Playing).unwrap();
+        wait_for_state(&pipeline, gst::State::Playing);
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    summary(
+        "start-stop-cycles",
+        &[
+            ("cycles", "20".to_string()),
+            ("duration-ms", started.elapsed().as_millis().to_string()),
+        ],
+    );
+}
+
+#[test]
+fn capture_five_seconds() {
+    let _camera = init();
+    let pipeline = gst::parse::launch("bigeyesrc ! fakesink name=sink signal-handoffs=true").unwrap();
+    let pipeline = pipeline.downcast::<gst::Bin>().unwrap();
+    let sink = pipeline.by_name("sink").unwrap();
+
+    let timestamps = Arc::new(Mutex::new(Vec::new()));
+    let recorded = timestamps.clone();
+    sink.connect("handoff", false, move |args| {
+        let buffer = args[1].get::<gst::Buffer>().unwrap();
+        recorded.lock().unwrap().push(buffer.pts());
+        None
+    });
+
+    pipeline.set_state(gst::State::Playing).unwrap();
+    std::thread::sleep(Duration::from_secs(5));
+    pipeline.set_state(gst::State::Null).unwrap();
+
+    let timestamps = timestamps.lock().unwrap();
+    let monotonic = timestamps
+        .windows(2)
+        .all(|pair| matches!(pair, [Some(a), Some(b)] if a < b));
+    summary(
+        "capture-five-seconds",
+        &[
+            ("frames", timestamps.len().to_string()),
+            ("monotonic-pts", monotonic.to_string()),
+        ],
+    );
+    assert!(timestamps.len() >= 400, "only {} frames in 5 seconds", timestamps.len());
+    assert!(timestamps.iter().all(Option::is_some), "buffers without PTS");
+    assert!(monotonic, "PTS not strictly increasing");
+}
+
+#[test]
+fn caps_and_framerate() {
+    let _camera = init();
+    for (format, name) in [("mjpeg", "image/jpeg"), ("raw", "video/x-raw")] {
+        let mut h = Harness::new("bigeyesrc");
+        h.element().unwrap().set_property_from_str("format", format);
+        h.play();
+        let buffer = h.pull().expect("no buffer");
+
+        let caps = h.element().unwrap().static_pad("src").unwrap().current_caps().unwrap();
+        let s = caps.structure(0).unwrap();
+        summary(
+            "caps-and-framerate",
+            &[("format", format.to_string()), ("caps", format!("\"{}\"", caps))],
+        );
+        assert_eq!(s.name(), name);
+        assert_eq!(s.get::<i32>("width").unwrap(), 800);
+        assert_eq!(s.get::<i32>("height").unwrap(), 400);
+        assert_eq!(s.get::<gst::Fraction>("framerate").unwrap(), gst::Fraction::new(90, 1));
+        assert_eq!(buffer.duration(), Some(gst::ClockTime::SECOND / 90));
+        if format == "raw" {
+            assert_eq!(buffer.size(), 800 * 400 * 2);
+        }
+    }
+}
+
+#[test]
+fn control_property_round_trips() {
+    let _camera = init();
+    let mut h = Harness::new("bigeyesrc");
+    h.play();
+    h.pull().expect("no buffer");
+    let element = h.element().unwrap();
+
+    let values: [(&str, glib::Value); 7] = [
+        ("invert", true.to_value()),
+        ("normalize", true.to_value()),
+        ("gap-threshold", 120u32.to_value()),
+        ("brightness-threshold", 25.0f64.to_value()),
+        ("freeze-detection", true.to_value()),
+        ("freeze-threshold", 30u32.to_value()),
+        ("log-throttle", 250u32.to_value()),
+    ];
+    let mut failed = Vec::new();
+    for (name, value) in &values {
+        element.set_property_from_value(name, value);
+        let read = element.property_value(name);
+        if read.serialize().ok() != value.serialize().ok() {
+            failed.push(*name);
+        }
+    }
+
+    summary(
+        "control-property-round-trips",
+        &[
+            ("properties", values.len().to_string()),
+            ("failed", format!("\"{}\"", failed.join(","))),
+        ],
+    );
+    assert!(failed.is_empty(), "properties not read back: {:?}", failed);
+}
+
+#[test]
+fn reconnect_after_reset_device() {
+    let _camera = init();
+    let mut h = Harness::new("bigeyesrc");
+    h.play();
+    h.pull().expect("no buffer before the reset");
+    let element = h.element().unwrap();
+    let format = element.property::<Option<gst::Structure>>("active-format");
+
+    let started = Instant::now();
+    let reset = element.emit_by_name::<bool>("reset-device", &[]);
+    let reopened = started.elapsed();
+    assert!(reset, "reset-device failed");
+
+    // Frames queued before the reset may still arrive first
+    for _ in 0..10 {
+        h.pull().expect("no buffer after the reset");
+    }
+    summary(
+        "reconnect-after-reset-device",
+        &[
+            ("reopen-ms", reopened.as_millis().to_string()),
+            ("recovery-ms", started.elapsed().as_millis().to_string()),
+        ],
+    );
+    assert_eq!(element.property::<Option<gst::Structure>>("active-format"), format);
+}